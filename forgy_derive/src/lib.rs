@@ -1,77 +1,1179 @@
-use darling::{ast, util, FromDeriveInput, FromField};
-use proc_macro2::TokenStream;
-use quote::quote;
-use syn::DeriveInput;
+use darling::{ast, util, FromDeriveInput, FromField, FromMeta, FromVariant};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{ext::IdentExt, spanned::Spanned, DeriveInput};
 
 #[derive(FromDeriveInput)]
-#[darling(attributes(forgy))]
+#[darling(attributes(forgy), forward_attrs(repr))]
 struct BuildArgs {
     ident: syn::Ident,
 
-    data: ast::Data<util::Ignored, BuildField>,
+    attrs: Vec<syn::Attribute>,
+
+    generics: syn::Generics,
+
+    data: ast::Data<BuildVariant, BuildField>,
+
+    input: Option<TypeArg>,
+
+    /// Type parameters of `input` that aren't the struct's, like `Db` in `AppState<Db>`.
+    #[darling(default, rename = "generics")]
+    input_generics: InputParams,
+
+    /// Types the input must be `AsRef` of, for any input.
+    #[darling(default)]
+    requires: util::PathList,
+
+    /// The variant to construct when deriving on an enum.
+    variant: Option<syn::Ident>,
+
+    /// The value of the only field, as if it had `value`.
+    value: Option<syn::Expr>,
+
+    /// A `fn(&Self) -> bool` that must return true for the built value.
+    validate: Option<syn::Expr>,
+
+    /// The name `value` expressions bind the input as, instead of `input`.
+    rename_input: Option<syn::Ident>,
+
+    /// Call `OnBuilt::on_built` after the value is stored.
+    on_built: util::Flag,
+
+    /// Build a new value for every `Container::get` instead of storing one.
+    transient: util::Flag,
+
+    /// Share the stored value of the only field's type, which this wraps transparently.
+    cache_as: Option<TypeArg>,
+
+    /// Also generate an inherent `new` taking every field.
+    new: util::Flag,
+
+    /// Also generate an inherent `forgy_deps` describing each field.
+    describe: util::Flag,
 
-    input: Option<syn::Path>,
+    /// Mark the generated `build` as `#[inline]`.
+    inline: Option<Inline>,
+
+    /// Implement `TryBuild` instead of `Build`. Spelled `try` by users, see [rename_keywords].
+    try_build: util::Flag,
+
+    /// Extra predicates for the impl's where clause. Spelled `where` by users, see
+    /// [rename_keywords].
+    #[darling(default)]
+    where_clause: WherePredicates,
 }
 
-#[derive(FromField)]
+/// A type written as an expression, so `input = &Config` works as well as `input = Config`, and
+/// `collect = dyn Step` works.
+struct TypeArg(syn::Type);
+
+impl FromMeta for TypeArg {
+    fn from_expr(expr: &syn::Expr) -> darling::Result<Self> {
+        if let syn::Expr::Lit(lit) = expr {
+            return Self::from_value(&lit.lit);
+        }
+        syn::parse2(expr.to_token_stream())
+            .map(TypeArg)
+            .map_err(|e| darling::Error::custom(e).with_span(expr))
+    }
+
+    /// Types that aren't expressions, like `dyn Step`, are quoted by [quote_type_attr].
+    fn from_value(value: &syn::Lit) -> darling::Result<Self> {
+        match value {
+            syn::Lit::Str(s) => s
+                .parse()
+                .map(TypeArg)
+                .map_err(|e| darling::Error::custom(e).with_span(s)),
+            _ => Err(darling::Error::unexpected_lit_type(value)),
+        }
+    }
+}
+
+/// Type parameters with optional bounds, as in `generics(Db: Database, Cache)`.
+#[derive(Default)]
+struct InputParams(Vec<syn::TypeParam>);
+
+impl FromMeta for InputParams {
+    fn from_meta(item: &syn::Meta) -> darling::Result<Self> {
+        let list = item.require_list()?;
+        list.parse_args_with(
+            syn::punctuated::Punctuated::<syn::TypeParam, syn::Token![,]>::parse_terminated,
+        )
+        .map(|params| InputParams(params.into_iter().collect()))
+        .map_err(|e| darling::Error::custom(e).with_span(list))
+    }
+}
+
+/// `inline` or `inline(always)`.
+enum Inline {
+    Hint,
+    Always,
+}
+
+impl FromMeta for Inline {
+    fn from_word() -> darling::Result<Self> {
+        Ok(Inline::Hint)
+    }
+
+    fn from_list(items: &[ast::NestedMeta]) -> darling::Result<Self> {
+        match items {
+            [ast::NestedMeta::Meta(syn::Meta::Path(path))] if path.is_ident("always") => {
+                Ok(Inline::Always)
+            }
+            _ => Err(darling::Error::custom(
+                "expected `inline` or `inline(always)`",
+            )),
+        }
+    }
+}
+
+impl ToTokens for Inline {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(match self {
+            Inline::Hint => quote!(#[inline]),
+            Inline::Always => quote!(#[inline(always)]),
+        });
+    }
+}
+
+/// Where clause predicates in a string, as in `where = "I: Send + Sync"`.
+#[derive(Default)]
+struct WherePredicates(Vec<syn::WherePredicate>);
+
+impl FromMeta for WherePredicates {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        syn::parse::Parser::parse_str(
+            syn::punctuated::Punctuated::<syn::WherePredicate, syn::Token![,]>::parse_terminated,
+            value,
+        )
+        .map(|predicates| WherePredicates(predicates.into_iter().collect()))
+        .map_err(darling::Error::custom)
+    }
+}
+
+#[derive(FromVariant)]
 #[darling(attributes(forgy))]
+struct BuildVariant {
+    ident: syn::Ident,
+
+    fields: ast::Fields<BuildField>,
+}
+
+#[derive(FromField)]
+#[darling(attributes(forgy), forward_attrs(cfg))]
 struct BuildField {
     ident: Option<syn::Ident>,
 
+    ty: syn::Type,
+
+    attrs: Vec<syn::Attribute>,
+
     value: Option<syn::Expr>,
+
+    /// Use `value` if this is true, otherwise `else`.
+    value_if: Option<syn::Expr>,
+
+    /// Spelled `else` by users, see [rename_keywords].
+    else_value: Option<syn::Expr>,
+
+    /// Like `value`, with `input` a clone of the container's input.
+    value_owned: Option<syn::Expr>,
+
+    /// A function from the input to the field's value.
+    value_with: Option<syn::Path>,
+
+    default: util::Flag,
+
+    /// Not injected, like `default`.
+    skip: util::Flag,
+
+    /// Spelled `impl` by users, see [rename_keywords].
+    impl_ty: Option<syn::Path>,
+
+    name: Option<String>,
+
+    /// Build a new value instead of sharing the stored one.
+    build: util::Flag,
+
+    /// Build the field's own type, for embedding another `Build` struct by value.
+    flatten: util::Flag,
+
+    /// The dependency `map` is applied to.
+    from: Option<syn::Path>,
+
+    map: Option<syn::Expr>,
+
+    /// Convert the `from` dependency with `Into`.
+    into: util::Flag,
+
+    /// Read the field by name from a `ConfigSource` input.
+    config: util::Flag,
+
+    /// Every implementation of this trait object from `Container::get_all`.
+    collect: Option<TypeArg>,
+
+    /// Collect with `Container::get_provided` instead, including `provides` implementations.
+    provided: util::Flag,
+
+    /// Only initialize the field under this predicate, which must match the field's `#[cfg]`.
+    cfg: Option<CfgPredicate>,
+}
+
+/// The predicate of `cfg(...)`, as in `cfg(feature = "metrics")`.
+struct CfgPredicate(TokenStream);
+
+impl FromMeta for CfgPredicate {
+    fn from_meta(item: &syn::Meta) -> darling::Result<Self> {
+        Ok(CfgPredicate(item.require_list()?.tokens.clone()))
+    }
 }
 
 impl BuildArgs {
-    fn main(input: DeriveInput) -> darling::Result<TokenStream> {
+    fn main(mut input: DeriveInput) -> darling::Result<TokenStream> {
+        rename_keywords(&mut input);
         let args = BuildArgs::from_derive_input(&input)?;
 
-        let constructor = quote!(constructor);
+        // An unlikely name, so fields bound by name in `fields_initializer` and user expressions
+        // don't shadow it.
+        let constructor = quote!(__forgy_container);
+        let input_name = args
+            .rename_input
+            .clone()
+            .unwrap_or_else(|| Ident::new("input", Span::call_site()));
 
         let struct_name = args.ident;
-        let (input_generic, input_ty) = match args.input {
-            Some(i) => (quote!(), quote!(#i)),
-            None => (quote!(<I>), quote!(I)),
+        let (path, mut fields) = match args.data {
+            ast::Data::Struct(fields) => (quote!(Self), fields),
+            ast::Data::Enum(variants) => {
+                let Some(name) = args.variant else {
+                    return Err(darling::Error::custom(
+                        "deriving `Build` on an enum requires `#[forgy(variant = ...)]`",
+                    )
+                    .with_span(&struct_name));
+                };
+                let Some(variant) = variants.into_iter().find(|v| v.ident == name) else {
+                    return Err(darling::Error::custom(format!("no variant named `{name}`"))
+                        .with_span(&name));
+                };
+
+                (quote!(Self::#name), variant.fields)
+            }
+        };
+
+        if let Some(value) = args.value {
+            let [field] = fields.fields.as_mut_slice() else {
+                return Err(darling::Error::custom(
+                    "`value` on the struct requires exactly one field",
+                )
+                .with_span(&value));
+            };
+            if field.value.is_some() {
+                return Err(darling::Error::custom(
+                    "`value` on the struct conflicts with `value` on the field",
+                )
+                .with_span(&value));
+            }
+            field.value = Some(value);
+        }
+
+        if let Some(lifetime) = args.generics.lifetimes().next() {
+            return Err(darling::Error::custom(
+                "`Build` requires `'static`, so the struct can't have lifetime parameters",
+            )
+            .with_span(lifetime));
+        }
+
+        let mut generics = args.generics.clone();
+        let generic_input = args.input.is_none();
+        if let Some(param) = args.input_generics.0.first() {
+            if generic_input {
+                return Err(darling::Error::custom("`generics` requires `input`").with_span(param));
+            }
+        }
+        let input_params = args.input_generics.0.into_iter();
+        generics
+            .params
+            .extend(input_params.map(syn::GenericParam::Type));
+        let input_ty = match args.input {
+            Some(TypeArg(i)) if !args.requires.is_empty() => {
+                return Err(
+                    darling::Error::custom("`input` conflicts with `requires`").with_span(&i)
+                );
+            }
+            Some(TypeArg(i)) => quote!(#i),
+            None => {
+                let required = args.requires.iter();
+                generics.params.push(if args.requires.is_empty() {
+                    syn::parse_quote!(I)
+                } else {
+                    syn::parse_quote!(I: #(::core::convert::AsRef<#required>)+*)
+                });
+                quote!(I)
+            }
         };
 
-        let fields = args.data.take_struct().unwrap();
-        let initializer = if fields.is_unit() {
-            quote!()
-        } else if fields.is_tuple() {
-            let fields = fields.into_iter().map(|f| f.construct_expr(&constructor));
-            quote!( (#(#fields),*) )
+        if generic_input && fields.iter().any(|f| f.config.is_present()) {
+            generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(I: ::forgy::ConfigSource));
+        }
+        if generic_input && fields.iter().any(|f| f.value_owned.is_some()) {
+            generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(I: ::core::clone::Clone));
+        }
+        if generic_input && fields.iter().any(|f| f.provided.is_present()) {
+            generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(I: 'static));
+        }
+        // `Provider<T>` only builds from the input it was declared with.
+        let providers = fields
+            .iter()
+            .filter(|f| f.wrapper().is_some_and(|w| w == "Provider"));
+        for field in providers.filter(|_| generic_input) {
+            let ty = &field.ty;
+            generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(#ty: ::forgy::Build<I>));
+        }
+
+        if !args.where_clause.0.is_empty() {
+            generics
+                .make_where_clause()
+                .predicates
+                .extend(args.where_clause.0);
+        }
+
+        let fallible = args.try_build.is_present();
+        let build_trait = if fallible {
+            quote!(::forgy::TryBuild)
         } else {
-            let fields = fields.into_iter().map(|field| {
-                let expr = field.construct_expr(&constructor);
-                let ident = field.ident.unwrap();
-                quote!(#ident: #expr)
-            });
-            quote!( { #(#fields),* })
+            quote!(::forgy::Build)
         };
 
-        Ok(TokenStream::from(quote::quote! {
-            impl #input_generic ::forgy::Build<#input_ty> for #struct_name {
-                fn build(#constructor: &mut ::forgy::Container<#input_ty>) -> Self {
-                    Self #initializer
+        // Generic dependencies need to be buildable, concrete ones are checked where they're used.
+        if args.generics.type_params().next().is_some() {
+            let where_clause = generics.make_where_clause();
+            for dependency in fields.iter().filter_map(BuildField::dependency) {
+                where_clause
+                    .predicates
+                    .push(syn::parse_quote!(#dependency: #build_trait<#input_ty>));
+            }
+        }
+
+        // A `TryBuild<I>` impl for any `I` would overlap with the blanket impl for `Build<I>` types.
+        if fallible && generic_input {
+            return Err(
+                darling::Error::custom("`try` requires `input`").with_span(&args.try_build.span())
+            );
+        }
+
+        if fallible && args.transient.is_present() {
+            return Err(darling::Error::custom("`transient` conflicts with `try`")
+                .with_span(&args.transient.span()));
+        }
+
+        if fallible && args.on_built.is_present() {
+            return Err(darling::Error::custom("`on_built` conflicts with `try`")
+                .with_span(&args.on_built.span()));
+        }
+
+        if args.on_built.is_present() {
+            let (_, ty_generics, _) = args.generics.split_for_impl();
+            generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(#struct_name #ty_generics: ::forgy::OnBuilt<#input_ty>));
+        }
+
+        let shared = match &args.cache_as {
+            Some(TypeArg(shared)) => Some(cache_as(
+                shared,
+                &args.attrs,
+                &fields,
+                &constructor,
+                &input_ty,
+            )?),
+            None => None,
+        };
+        if let Some(TypeArg(shared)) = &args.cache_as {
+            // The shared value is used as is, so nothing runs after building it.
+            let conflict = if fallible {
+                Some("try")
+            } else if args.on_built.is_present() {
+                Some("on_built")
+            } else if args.validate.is_some() {
+                Some("validate")
+            } else {
+                None
+            };
+            if let Some(conflict) = conflict {
+                return Err(darling::Error::custom(format!(
+                    "`cache_as` conflicts with `{conflict}`"
+                ))
+                .with_span(shared));
+            }
+        }
+
+        let mut inherent = Vec::new();
+        if args.new.is_present() {
+            inherent.push(new_fn(&fields, &path));
+        }
+        if args.describe.is_present() {
+            inherent.push(describe_fn(&fields));
+        }
+        let inherent_impl = (!inherent.is_empty()).then(|| {
+            let (impl_generics, ty_generics, where_clause) = args.generics.split_for_impl();
+            quote! {
+                impl #impl_generics #struct_name #ty_generics #where_clause {
+                    #(#inherent)*
+                }
+            }
+        });
+
+        let (body, get_shared) = match shared {
+            Some((body, get_shared)) => (body, Some(get_shared)),
+            None => (
+                fields_initializer(&path, fields, &constructor, &input_name, fallible)?,
+                None,
+            ),
+        };
+        let body = match &args.validate {
+            Some(validate) => {
+                let message = format!(
+                    "`{struct_name}` failed validation `{}`",
+                    validate.to_token_stream()
+                );
+                let fail = if fallible {
+                    quote!(return ::core::result::Result::Err(#message.into()))
+                } else {
+                    quote!(panic!("{}", #message))
+                };
+                quote!({
+                    let value = #body;
+                    let validate: fn(&Self) -> bool = #validate;
+                    if !validate(&value) {
+                        #fail;
+                    }
+                    value
+                })
+            }
+            None => body,
+        };
+        let after_build = args.on_built.is_present().then(|| {
+            quote! {
+                fn after_build(
+                    self: &::std::sync::Arc<Self>,
+                    container: &mut ::forgy::Container<#input_ty>,
+                ) {
+                    ::forgy::OnBuilt::<#input_ty>::on_built(self, container)
+                }
+            }
+        });
+        let transient = args.transient.is_present().then(|| {
+            quote!(
+                const TRANSIENT: bool = true;
+            )
+        });
+        let inline = &args.inline;
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let (_, ty_generics, _) = args.generics.split_for_impl();
+
+        let build_impl = if fallible {
+            quote! {
+                impl #impl_generics ::forgy::TryBuild<#input_ty> for #struct_name #ty_generics #where_clause {
+                    #inline
+                    fn try_build(
+                        #constructor: &mut ::forgy::Container<#input_ty>,
+                    ) -> ::core::result::Result<Self, ::forgy::BuildError> {
+                        ::core::result::Result::Ok(#body)
+                    }
+                }
+            }
+        } else {
+            quote! {
+                impl #impl_generics ::forgy::Build<#input_ty> for #struct_name #ty_generics #where_clause {
+                    #transient
+
+                    #inline
+                    fn build(#constructor: &mut ::forgy::Container<#input_ty>) -> Self {
+                        #body
+                    }
+
+                    #after_build
+
+                    #get_shared
                 }
             }
-        }))
+        };
+
+        Ok(quote! {
+            #build_impl
+            #inherent_impl
+        })
+    }
+}
+
+/// `pub fn new` taking every field as a parameter in order, named `field0`, `field1`... for tuple
+/// fields.
+fn new_fn(fields: &ast::Fields<BuildField>, path: &TokenStream) -> TokenStream {
+    let params: Vec<Ident> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| f.ident.clone().unwrap_or_else(|| format_ident!("field{i}")))
+        .collect();
+    let tys = fields.iter().map(|f| &f.ty);
+    let body = if fields.is_unit() {
+        quote!()
+    } else if fields.is_tuple() {
+        quote!( (#(#params),*) )
+    } else {
+        quote!( { #(#params),* } )
+    };
+
+    quote! {
+        #[allow(clippy::new_without_default, clippy::too_many_arguments)]
+        pub fn new(#(#params: #tys),*) -> Self {
+            #path #body
+        }
+    }
+}
+
+/// Construct `path` from its fields, computing each into its own local first so a field's
+/// borrows of the container end before the next field's expression. Fields are computed
+/// top-to-bottom, and each named field is bound by reference for the expressions after it.
+fn fields_initializer(
+    path: &TokenStream,
+    fields: ast::Fields<BuildField>,
+    constructor: &TokenStream,
+    input: &Ident,
+    fallible: bool,
+) -> darling::Result<TokenStream> {
+    let is_unit = fields.is_unit();
+    let is_tuple = fields.is_tuple();
+
+    let mut locals = Vec::new();
+    let mut idents = Vec::new();
+    let mut cfgs = Vec::new();
+    let mut stmts = Vec::new();
+    for (i, field) in fields.into_iter().enumerate() {
+        let expr = field.construct_expr(constructor, input, fallible)?;
+        let cfg = field.cfg_attr()?;
+        let local = Ident::new(&format!("field{i}"), Span::mixed_site());
+        stmts.push(quote!(#cfg let #local = #expr;));
+        if let Some(ident) = &field.ident {
+            stmts.push(quote!(#cfg #[allow(unused)] let #ident = &#local;));
+        }
+        locals.push(local);
+        idents.push(field.ident);
+        cfgs.push(cfg);
+    }
+
+    let init = if is_unit {
+        quote!()
+    } else if is_tuple {
+        quote!( (#(#locals),*) )
+    } else {
+        let idents = idents.iter().flatten();
+        quote!( { #(#cfgs #idents: #locals),* } )
+    };
+    Ok(quote!({
+        #(#stmts)*
+        #path #init
+    }))
+}
+
+/// The body of `Build::build` and `Build::get_shared` for a `#[repr(transparent)]` struct whose only
+/// field is a `shared`. `get_shared` casts the stored `Arc<shared>` to an `Arc<Self>`.
+fn cache_as(
+    shared: &syn::Type,
+    attrs: &[syn::Attribute],
+    fields: &ast::Fields<BuildField>,
+    constructor: &TokenStream,
+    input_ty: &TokenStream,
+) -> darling::Result<(TokenStream, TokenStream)> {
+    let transparent = attrs.iter().any(|attr| {
+        let mut transparent = false;
+        let _ = attr.parse_nested_meta(|meta| {
+            transparent |= meta.path.is_ident("transparent");
+            Ok(())
+        });
+        transparent
+    });
+    if !transparent {
+        return Err(
+            darling::Error::custom("`cache_as` requires `#[repr(transparent)]`").with_span(shared),
+        );
+    }
+
+    let [field] = fields.fields.as_slice() else {
+        return Err(
+            darling::Error::custom("`cache_as` requires exactly one field").with_span(shared),
+        );
+    };
+    let init = match &field.ident {
+        Some(ident) => quote!(Self { #ident: field }),
+        None => quote!(Self(field)),
+    };
+
+    let body = quote!({
+        let field = #constructor.build::<#shared>();
+        #init
+    });
+    let get_shared = quote! {
+        fn get_shared(
+            container: &mut ::forgy::Container<#input_ty>,
+        ) -> ::core::result::Result<
+            ::core::option::Option<::std::sync::Arc<Self>>,
+            ::forgy::ForgyError,
+        > {
+            // Only compiles if the field is exactly a `#shared`.
+            let _: fn(#shared) -> Self = |field| #init;
+            let shared = container.try_get::<#shared>()?;
+            // SAFETY: Self is `#[repr(transparent)]` over its only field, which is a `#shared`, so
+            // they have the same layout.
+            ::core::result::Result::Ok(::core::option::Option::Some(unsafe {
+                ::std::sync::Arc::from_raw(::std::sync::Arc::into_raw(shared).cast::<Self>())
+            }))
+        }
+    };
+    Ok((body, get_shared))
+}
+
+/// `pub fn forgy_deps` listing each field's name, or index for tuple fields, with the name of the
+/// type it's resolved as.
+fn describe_fn(fields: &ast::Fields<BuildField>) -> TokenStream {
+    let entries = fields.iter().enumerate().map(|(i, f)| {
+        let name = match &f.ident {
+            Some(ident) => ident.unraw().to_string(),
+            None => i.to_string(),
+        };
+        let ty = f.resolved_type();
+        quote!((#name, ::core::any::type_name::<#ty>()))
+    });
+
+    quote! {
+        pub fn forgy_deps() -> ::std::vec::Vec<(&'static str, &'static str)> {
+            ::std::vec![#(#entries),*]
+        }
     }
 }
 
 impl BuildField {
-    fn construct_expr(&self, constructor: &TokenStream) -> TokenStream {
-        if let Some(expr) = &self.value {
-            return quote!({
+    /// The `T` of an `Arc<T>`, `Box<T>` or `Rc<T>` dependency field, or the type of a `flatten`
+    /// field.
+    fn dependency(&self) -> Option<&syn::Type> {
+        if self.value.is_some()
+            || self.value_with.is_some()
+            || self.value_owned.is_some()
+            || self.default.is_present()
+            || self.skip.is_present()
+            || self.impl_ty.is_some()
+            || self.name.is_some()
+            || self.from.is_some()
+            || self.config.is_present()
+            || self.collect.is_some()
+        {
+            return None;
+        }
+
+        // A `Provider<T>` is built by value, and is only `Build` when its T is.
+        if self.flatten.is_present() || self.wrapper().is_some_and(|w| w == "Provider") {
+            return Some(&self.ty);
+        }
+
+        // A `WeakRef<T>` or `Option<Arc<T>>` never builds its T.
+        if self
+            .wrapper()
+            .is_some_and(|w| w == "WeakRef" || w == "Option")
+        {
+            return None;
+        }
+
+        let syn::Type::Path(path) = &self.ty else {
+            return None;
+        };
+        let syn::PathArguments::AngleBracketed(args) = &path.path.segments.last()?.arguments else {
+            return None;
+        };
+        match args.args.first()? {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }
+    }
+
+    /// `value` expressions bind the input as `input`. With `fallible`, `Arc<T>` dependencies are
+    /// resolved with `Container::get_try` and `?`.
+    fn construct_expr(
+        &self,
+        constructor: &TokenStream,
+        input: &Ident,
+        fallible: bool,
+    ) -> darling::Result<TokenStream> {
+        if self.build.is_present() && (self.dependency().is_none() || self.flatten.is_present()) {
+            return Err(
+                darling::Error::custom("`build` conflicts with other field attributes")
+                    .with_span(&self.build.span()),
+            );
+        }
+
+        if let Some(expr) = &self.value_owned {
+            if self.exclusive_conflicts() {
+                return Err(darling::Error::custom(
+                    "`value_owned` conflicts with other field attributes",
+                )
+                .with_span(expr));
+            }
+            return Ok(quote!({
+                #[allow(unused)]
+                let container = &mut *#constructor;
                 #[allow(unused)]
-                let input = #constructor.input();
+                let #input = ::core::clone::Clone::clone(container.input());
                 #expr
+            }));
+        }
+
+        if let Some(f) = &self.value_with {
+            if self.exclusive_conflicts() {
+                return Err(darling::Error::custom(
+                    "`value_with` conflicts with other field attributes",
+                )
+                .with_span(f));
+            }
+            return Ok(quote!(#f(#constructor.input())));
+        }
+
+        if let Some(TypeArg(ty)) = &self.collect {
+            if self.exclusive_conflicts() {
+                return Err(darling::Error::custom(
+                    "`collect` conflicts with other field attributes",
+                )
+                .with_span(ty));
+            }
+            if self.provided.is_present() {
+                return Ok(quote!(#constructor.get_provided::<#ty>()));
+            }
+            return Ok(quote!(#constructor.get_all::<#ty>()));
+        }
+        if self.provided.is_present() {
+            return Err(darling::Error::custom("`provided` requires `collect`")
+                .with_span(&self.provided.span()));
+        }
+
+        if self.skip.is_present() {
+            if self.exclusive_conflicts() {
+                return Err(
+                    darling::Error::custom("`skip` conflicts with other field attributes")
+                        .with_span(&self.skip.span()),
+                );
+            }
+            return Ok(quote!(::core::default::Default::default()));
+        }
+
+        if self.config.is_present() {
+            if self.exclusive_conflicts() {
+                return Err(darling::Error::custom(
+                    "`config` conflicts with other field attributes",
+                )
+                .with_span(&self.config.span()));
+            }
+            let Some(ident) = &self.ident else {
+                return Err(darling::Error::custom("`config` requires a named field")
+                    .with_span(&self.config.span()));
+            };
+            let name = ident.unraw().to_string();
+            return Ok(quote!(::forgy::ConfigSource::field(#constructor.input(), #name)));
+        }
+
+        match (&self.from, &self.map) {
+            (Some(_), Some(_)) if self.into.is_present() => {
+                return Err(darling::Error::custom("`into` conflicts with `map`")
+                    .with_span(&self.into.span()));
+            }
+            (Some(from), Some(map)) => {
+                return Ok(quote!({
+                    #[allow(unused)]
+                    let dep = #constructor.get::<#from>();
+                    #map
+                }));
+            }
+            (Some(from), None) if self.into.is_present() => {
+                return Ok(quote!(::core::convert::Into::into(#constructor.get::<#from>())));
+            }
+            (Some(from), None) => {
+                return Err(
+                    darling::Error::custom("`from` requires `map` or `into`").with_span(from)
+                );
+            }
+            (None, Some(map)) => {
+                return Err(darling::Error::custom("`map` requires `from`").with_span(map));
+            }
+            (None, None) if self.into.is_present() => {
+                return Err(
+                    darling::Error::custom("`into` requires `from`").with_span(&self.into.span())
+                );
+            }
+            (None, None) => {}
+        }
+
+        let value = self.value_expr()?;
+
+        if let Some(name) = &self.name {
+            if self.default.is_present() || self.impl_ty.is_some() {
+                return Err(darling::Error::custom(
+                    "`name` can only be combined with `value`",
+                ));
+            }
+            return Ok(match &value {
+                Some(expr) => {
+                    let value = value_block(&quote!(container), input, expr);
+                    quote!(#constructor.get_named_or_build_with(#name, |container| #value))
+                }
+                None => quote!(#constructor.get_named(#name)),
             });
         }
 
-        quote!(#constructor.get())
+        if self.default.is_present() {
+            if self.value.is_some() {
+                return Err(darling::Error::custom("`default` conflicts with `value`")
+                    .with_span(&self.default.span()));
+            }
+            return Ok(quote!(::core::default::Default::default()));
+        }
+
+        if let Some(ty) = &self.impl_ty {
+            if self.value.is_some() {
+                return Err(darling::Error::custom("`impl` conflicts with `value`").with_span(ty));
+            }
+            return Ok(quote!(#constructor.get::<#ty>()));
+        }
+
+        if let Some(expr) = &value {
+            return Ok(value_block(constructor, input, expr));
+        }
+
+        // Point unsatisfied `Build` bounds at the field's type.
+        let span = self.ty.span();
+        let constructor: TokenStream = constructor
+            .clone()
+            .into_iter()
+            .map(|mut t| {
+                t.set_span(span);
+                t
+            })
+            .collect();
+        Ok(match self.wrapper() {
+            _ if self.flatten.is_present() => quote_spanned!(span=> #constructor.build()),
+            Some(w) if w == "Box" => {
+                quote_spanned!(span=> ::std::boxed::Box::new(#constructor.build()))
+            }
+            Some(w) if w == "Rc" => quote_spanned!(span=> ::std::rc::Rc::new(#constructor.build())),
+            Some(w) if w == "WeakRef" || w == "Provider" => {
+                quote_spanned!(span=> #constructor.build())
+            }
+            Some(w) if w == "Option" => quote_spanned!(span=> #constructor.get_optional()),
+            _ if self.build.is_present() => {
+                quote_spanned!(span=> ::std::sync::Arc::new(#constructor.build()))
+            }
+            _ if fallible => quote_spanned!(span=> #constructor.get_try()?),
+            _ => quote_spanned!(span=> #constructor.get()),
+        })
+    }
+
+    /// The type the field is resolved as, like `T` for an `Arc<T>` dependency or the `impl` type.
+    fn resolved_type(&self) -> TokenStream {
+        if let Some(path) = self.impl_ty.as_ref().or(self.from.as_ref()) {
+            return quote!(#path);
+        }
+        if let Some(TypeArg(ty)) = &self.collect {
+            return quote!(#ty);
+        }
+        let ty = self.dependency().unwrap_or(&self.ty);
+        quote!(#ty)
+    }
+
+    /// Whether more than one attribute that sets the field's value is present, for attributes that
+    /// can't be combined with any other.
+    fn exclusive_conflicts(&self) -> bool {
+        let present = [
+            self.value.is_some(),
+            self.value_with.is_some(),
+            self.value_owned.is_some(),
+            self.default.is_present(),
+            self.skip.is_present(),
+            self.impl_ty.is_some(),
+            self.name.is_some(),
+            self.from.is_some(),
+            self.config.is_present(),
+            self.collect.is_some(),
+        ];
+        present.into_iter().filter(|p| *p).count() > 1
+    }
+
+    /// `#[cfg(...)]` for the field's initializer, if it has `cfg`. The field needs the same
+    /// `#[cfg(...)]`, so the struct has the field exactly when it's initialized.
+    fn cfg_attr(&self) -> darling::Result<Option<TokenStream>> {
+        let Some(CfgPredicate(predicate)) = &self.cfg else {
+            return Ok(None);
+        };
+        if self.ident.is_none() {
+            return Err(darling::Error::custom("`cfg` requires a named field").with_span(predicate));
+        }
+
+        let matching = self.attrs.iter().any(|attr| match &attr.meta {
+            syn::Meta::List(list) => list.tokens.to_string() == predicate.to_string(),
+            _ => false,
+        });
+        if !matching {
+            return Err(darling::Error::custom(format!(
+                "`cfg` requires the field to have `#[cfg({predicate})]`"
+            ))
+            .with_span(predicate));
+        }
+        Ok(Some(quote!(#[cfg(#predicate)])))
+    }
+
+    /// The `value` expression, as `if value_if { value } else { else }` when it's conditional.
+    fn value_expr(&self) -> darling::Result<Option<syn::Expr>> {
+        match (&self.value, &self.value_if, &self.else_value) {
+            (value, None, None) => Ok(value.clone()),
+            (Some(value), Some(cond), Some(else_value)) => Ok(Some(syn::parse_quote!(
+                if #cond { #value } else { #else_value }
+            ))),
+            (_, Some(cond), _) => Err(darling::Error::custom(
+                "`value_if` requires `value` and `else`",
+            )
+            .with_span(cond)),
+            (_, None, Some(else_value)) => {
+                Err(darling::Error::custom("`else` requires `value_if`").with_span(else_value))
+            }
+        }
+    }
+
+    /// The name of the field's outer type, like `Arc` in `Arc<T>`.
+    fn wrapper(&self) -> Option<&syn::Ident> {
+        let syn::Type::Path(path) = &self.ty else {
+            return None;
+        };
+        Some(&path.path.segments.last()?.ident)
+    }
+}
+
+fn value_block(constructor: &TokenStream, input: &Ident, expr: &syn::Expr) -> TokenStream {
+    quote!({
+        #[allow(unused)]
+        let container = &mut *#constructor;
+        #[allow(unused)]
+        let #input = container.input();
+        #expr
+    })
+}
+
+/// `impl`, `else`, `try` and `where` are keywords, so darling can't parse them as field names.
+/// Rewrite them to `impl_ty`, `else_value`, `try_build` and `where_clause` first.
+fn rename_keywords(input: &mut DeriveInput) {
+    let fields: Vec<&mut syn::Field> = match &mut input.data {
+        syn::Data::Struct(data) => data.fields.iter_mut().collect(),
+        syn::Data::Enum(data) => data
+            .variants
+            .iter_mut()
+            .flat_map(|v| v.fields.iter_mut())
+            .collect(),
+        syn::Data::Union(_) => return,
+    };
+
+    let field_attrs = fields.into_iter().flat_map(|f| f.attrs.iter_mut());
+    for attr in field_attrs.filter(|a| a.path().is_ident("forgy")) {
+        rename_keyword(attr, "impl", "impl_ty");
+        rename_keyword(attr, "else", "else_value");
+        quote_type_attr(attr, &["impl_ty", "from", "collect"]);
+    }
+    for attr in input
+        .attrs
+        .iter_mut()
+        .filter(|a| a.path().is_ident("forgy"))
+    {
+        rename_keyword(attr, "try", "try_build");
+        rename_keyword(attr, "where", "where_clause");
+        quote_type_attr(attr, &["input", "cache_as"]);
+    }
+}
+
+/// `collect = dyn Step` or `input = AppState<Db>` doesn't parse as an expression, so darling can't
+/// read it. Rewrite the type after each of `keys` to a string, as in `collect = "dyn Step"`, first.
+/// Runs after [rename_keyword], so `impl` is `impl_ty`.
+fn quote_type_attr(attr: &mut syn::Attribute, keys: &[&str]) {
+    let syn::Meta::List(list) = &mut attr.meta else {
+        return;
+    };
+
+    let mut tokens = std::mem::take(&mut list.tokens).into_iter().peekable();
+    let mut rewritten = TokenStream::new();
+    while let Some(t) = tokens.next() {
+        let is_key = matches!(&t, TokenTree::Ident(i) if keys.iter().any(|k| i == k));
+        rewritten.extend([t]);
+        if !is_key {
+            continue;
+        }
+        match tokens.peek() {
+            Some(TokenTree::Punct(p)) if p.as_char() == '=' => {
+                rewritten.extend(tokens.next());
+            }
+            _ => continue,
+        }
+
+        let mut ty = TokenStream::new();
+        let mut depth = 0;
+        let mut arrow = false;
+        while let Some(t) =
+            tokens.next_if(|t| depth > 0 || !matches!(t, TokenTree::Punct(p) if p.as_char() == ','))
+        {
+            if let TokenTree::Punct(p) = &t {
+                match p.as_char() {
+                    '<' => depth += 1,
+                    '>' if !arrow => depth -= 1,
+                    _ => {}
+                }
+                arrow = p.as_char() == '-' && p.spacing() == proc_macro2::Spacing::Joint;
+            } else {
+                arrow = false;
+            }
+            ty.extend([t]);
+        }
+        let span = ty
+            .clone()
+            .into_iter()
+            .next()
+            .map_or(Span::call_site(), |t| t.span());
+        let lit = syn::LitStr::new(&ty.to_string(), span);
+        rewritten.extend(lit.to_token_stream());
+    }
+    list.tokens = rewritten;
+}
+
+/// Only renames `keyword` where it's an attribute name, followed by `=`, `,` or nothing, so keywords
+/// in expressions like `value = if a { 1 } else { 2 }` are left alone.
+fn rename_keyword(attr: &mut syn::Attribute, keyword: &str, name: &str) {
+    if let syn::Meta::List(list) = &mut attr.meta {
+        let mut tokens = std::mem::take(&mut list.tokens).into_iter().peekable();
+        let mut renamed = TokenStream::new();
+        while let Some(t) = tokens.next() {
+            let is_name = match tokens.peek() {
+                Some(TokenTree::Punct(p)) => matches!(p.as_char(), '=' | ','),
+                Some(_) => false,
+                None => true,
+            };
+            renamed.extend([match t {
+                TokenTree::Ident(i) if is_name && i == keyword => {
+                    TokenTree::Ident(Ident::new(name, i.span()))
+                }
+                t => t,
+            }]);
+        }
+        list.tokens = renamed;
     }
 }
 
+/// Derive `forgy::Build`.
+///
+/// `#[forgy(value = ...)]` expressions can use `input`, the container's input, and `container`,
+/// the container itself. `input` borrows `container`, so to use both, resolve dependencies first
+/// and then read the input through the container:
+/// `#[forgy(value = { let client = container.get::<Client>(); container.input().url(&client) })]`.
+///
+/// On a struct with one field, such as a newtype, `#[forgy(value = 8080)]` on the struct is the same
+/// as on the field.
+///
+/// Fields are computed top-to-bottom, and expressions can refer to earlier named fields by
+/// reference: `#[forgy(value = a + b)]`. An earlier field shadows a function, const or static with
+/// the same name, so refer to those by path, as in `self::timeout()`.
+///
+/// A field with `#[cfg(feature = "metrics")]` is only initialized when it exists. To spell out the
+/// condition on the initializer too, add `#[forgy(cfg(feature = "metrics"))]`, which must match
+/// the field's `#[cfg]`.
+///
+/// `#[forgy(rename_input = config)]` binds the input as `config` instead of `input` in every
+/// expression.
+///
+/// Dependency fields can be `Arc<T>`, shared through `Container::get`, or `Box<T>` or `Rc<T>`,
+/// newly built with `Container::build`. `#[forgy(build)]` builds a new value for an `Arc<T>` field
+/// too. A `WeakRef<T>` field refers back to a T that may still be being built. An `Option<Arc<T>>`
+/// field uses `Container::get_optional`, so it's `None` unless a T was inserted or registered. A
+/// `Provider<T>` field resolves a T each time its `get` is called.
+///
+/// `#[forgy(impl = Memory<u8>)]` resolves an `Arc<dyn Store>` field as the stored `Memory<u8>`.
+/// Types in `impl` and `from` can be generic or type aliases, like any dependency type.
+///
+/// `#[forgy(collect = dyn Step)]` fills a `Vec<Arc<dyn Step>>` field with `Container::get_all`,
+/// in the order the implementations were registered. `#[forgy(collect = dyn Step, provided)]` uses
+/// `Container::get_provided` instead, to include implementations added with `provides`, and
+/// requires the `registry` feature.
+///
+/// `#[forgy(skip)]` or `#[forgy(default)]` uses `Default::default()` for a field that isn't
+/// injected, like internal state.
+///
+/// `#[forgy(flatten)]` builds a field of another `Build` type by value, to share a set of
+/// dependencies between structs.
+///
+/// `#[forgy(value_owned = ...)]` is like `value`, but `input` is a clone of the input, so the
+/// expression can use `input` and `container` together. The input must implement `Clone`:
+/// `#[forgy(value_owned = format!("{}{}", input.url, container.get::<Client>().prefix))]`.
+///
+/// `#[forgy(value_if = input.fast, value = ..., else = ...)]` uses `value` when the condition is
+/// true and `else` otherwise. Both the condition and the values can use `input` and `container`.
+///
+/// `#[forgy(value_with = make_pool)]` uses `make_pool(input)` as the field's value, for a function
+/// taking a reference to the input.
+///
+/// `#[forgy(name = "...")]` resolves an `Arc<T>` field with `Container::get_named`, or computes
+/// and stores it under that name when combined with `value`.
+///
+/// `#[forgy(from = Config, map = dep.workers)]` resolves the `Arc<Config>` as `dep` and uses the
+/// `map` expression as the field's value. `#[forgy(from = Postgres, into)]` converts the
+/// `Arc<Postgres>` with `Into` instead.
+///
+/// `#[forgy(input = ..., try)]` implements `TryBuild` instead of `Build`, so `value` expressions can use `?`
+/// with any error convertible to `BuildError`. `Arc<T>` dependencies are resolved with
+/// `Container::get_try`.
+///
+/// `#[forgy(new)]` also generates `pub fn new` taking every field as a parameter, in order,
+/// including fields with `value`, `default` or other attributes, for constructing without a
+/// container.
+///
+/// `#[forgy(transient)]` makes `Container::get` build a new value every time instead of storing
+/// one.
+///
+/// `#[forgy(where = "I: Send + Sync")]` adds predicates to the generated impl's where clause, such
+/// as bounds on the generic input `I`.
+///
+/// `#[forgy(validate = |s| !s.url.is_empty())]` checks the built value with a `fn(&Self) -> bool`,
+/// panicking if it returns false, or returning an error with `try`.
+///
+/// `#[forgy(inline)]` or `#[forgy(inline(always))]` marks the generated `build` as `#[inline]` or
+/// `#[inline(always)]`.
+///
+/// `#[forgy(describe)]` also generates `pub fn forgy_deps() -> Vec<(&'static str, &'static str)>`
+/// listing each field's name with the name of the type it's resolved as, like `T` for an `Arc<T>`.
+///
+/// `#[forgy(cache_as = Db)]` on a `#[repr(transparent)]` struct with a single `Db` field makes
+/// `Container::get` share the stored `Db`, so several wrappers of it share one value.
+///
+/// `#[forgy(on_built)]` calls `OnBuilt::on_built` after `Container::get` stores a new value.
+///
+/// With the `serde` feature, `#[forgy(config)]` reads a named field from an input implementing
+/// `ConfigSource`, such as a `serde_json::Value` object with a key of the same name.
+///
+/// `#[forgy(input = AppState<Db>, generics(Db: Database))]` implements `Build<AppState<Db>>` for
+/// every `Db: Database`, for inputs with type parameters the struct doesn't have.
+///
+/// `#[forgy(requires(Config, Secrets))]` implements `Build<I>` for any input
+/// `I: AsRef<Config> + AsRef<Secrets>`. Value expressions pick one with a type annotation:
+/// `#[forgy(value = { let config: &Config = input.as_ref(); config.name.clone() })]`.
 #[proc_macro_derive(Build, attributes(forgy))]
 pub fn derive_build(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let derive_input = syn::parse_macro_input!(input as DeriveInput);
@@ -80,3 +1182,46 @@ pub fn derive_build(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         Err(e) => proc_macro::TokenStream::from(e.write_errors()),
     }
 }
+
+/// Add the implementation in an `impl Trait for Type` block to
+/// `Container::get_provided::<dyn Trait>()`, found at link time. Requires the `registry` feature.
+///
+/// `#[forgy::provides(dyn Handler)]` gets the Type from a `Container<()>`, and
+/// `#[forgy::provides(dyn Handler, input = Config)]` from a `Container<Config>`.
+#[proc_macro_attribute]
+pub fn provides(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let parser = |input: syn::parse::ParseStream| {
+        let provided: syn::Type = input.parse()?;
+        let mut input_ty: syn::Type = syn::parse_quote!(());
+        if input.parse::<Option<syn::Token![,]>>()?.is_some() {
+            let key: Ident = input.parse()?;
+            if key != "input" {
+                return Err(syn::Error::new(key.span(), "expected `input = ...`"));
+            }
+            input.parse::<syn::Token![=]>()?;
+            input_ty = input.parse()?;
+        }
+        Ok((provided, input_ty))
+    };
+    let (provided, input_ty) = syn::parse_macro_input!(attr with parser);
+    let item_impl = syn::parse_macro_input!(item as syn::ItemImpl);
+    let self_ty = &item_impl.self_ty;
+
+    proc_macro::TokenStream::from(quote! {
+        #item_impl
+
+        ::forgy::inventory::submit! {
+            ::forgy::Registration {
+                ty: || ::core::any::TypeId::of::<#provided>(),
+                resolve: |container| {
+                    let container = container.downcast_mut::<::forgy::Container<#input_ty>>()?;
+                    let provided: ::std::sync::Arc<#provided> = container.get::<#self_ty>();
+                    ::core::option::Option::Some(::std::boxed::Box::new(provided))
+                },
+            }
+        }
+    })
+}
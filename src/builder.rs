@@ -0,0 +1,113 @@
+use crate::Container;
+
+type Step<I> = Box<dyn FnOnce(&mut Container<I>)>;
+
+/// Sets up a [Container] before anything is built from it.
+///
+/// Steps are applied in the order they were added.
+pub struct ContainerBuilder<I = ()> {
+    input: Option<I>,
+    steps: Vec<Step<I>>,
+}
+
+impl<I> ContainerBuilder<I> {
+    /// Construct a new ContainerBuilder with no input.
+    pub fn new() -> ContainerBuilder<I> {
+        ContainerBuilder {
+            input: None,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Use `input` as the Container's input.
+    pub fn input(mut self, input: I) -> Self {
+        self.input = Some(input);
+        self
+    }
+
+    /// Store a T, as with [Container::insert].
+    pub fn insert<T: 'static>(mut self, value: T) -> Self {
+        self.steps.push(Box::new(|container| {
+            container.insert(value);
+        }));
+        self
+    }
+
+    /// Store a T as `name`, as with [Container::insert_named].
+    pub fn insert_named<T: 'static>(mut self, name: &str, value: T) -> Self {
+        let name = name.to_string();
+        self.steps.push(Box::new(move |container| {
+            container.insert_named(&name, value);
+        }));
+        self
+    }
+
+    /// Use `f` the first time a T is built, as with [Container::register].
+    pub fn register<T: 'static>(
+        mut self,
+        f: impl FnOnce(&mut Container<I>) -> T + 'static,
+    ) -> Self {
+        self.steps.push(Box::new(|container| container.register(f)));
+        self
+    }
+
+    /// Construct the Container.
+    ///
+    /// Panics if no input was given with [ContainerBuilder::input].
+    pub fn build(self) -> Container<I> {
+        let input = self.input.expect("ContainerBuilder requires an input");
+        let mut container = Container::new(input);
+        for step in self.steps {
+            step(&mut container);
+        }
+        container
+    }
+}
+
+impl<I> Default for ContainerBuilder<I> {
+    fn default() -> Self {
+        ContainerBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::Build;
+
+    struct Clock(u64);
+
+    impl Build for Clock {
+        fn build(_: &mut Container) -> Self {
+            unreachable!("overridden")
+        }
+    }
+
+    struct Scheduler {
+        clock: Arc<Clock>,
+    }
+
+    impl Build for Scheduler {
+        fn build(container: &mut Container) -> Self {
+            Scheduler {
+                clock: container.get(),
+            }
+        }
+    }
+
+    #[test]
+    fn builds_with_override() {
+        let mut c = ContainerBuilder::new()
+            .input(())
+            .insert(Clock(42))
+            .insert_named("region", "eu".to_string())
+            .build();
+
+        let scheduler: Arc<Scheduler> = c.get();
+        assert_eq!(scheduler.clock.0, 42);
+        let region: Arc<String> = c.get_named_or_build_with("region", |_| unreachable!());
+        assert_eq!(*region, "eu");
+    }
+}
@@ -0,0 +1,27 @@
+use serde::de::DeserializeOwned;
+
+/// An input that `#[forgy(config)]` fields are read from by name.
+///
+/// Implemented for `serde_json::Value` objects. Any `Serialize` config can be converted with
+/// `serde_json::to_value`.
+pub trait ConfigSource {
+    /// Deserialize the field called `name`.
+    ///
+    /// Panics if it is missing or is not a valid T.
+    fn field<T: DeserializeOwned>(&self, name: &str) -> T;
+}
+
+impl ConfigSource for serde_json::Value {
+    fn field<T: DeserializeOwned>(&self, name: &str) -> T {
+        let value = self
+            .get(name)
+            .unwrap_or_else(|| panic!("missing config field `{name}`"));
+        T::deserialize(value).unwrap_or_else(|e| panic!("invalid config field `{name}`: {e}"))
+    }
+}
+
+impl<C: ConfigSource + ?Sized> ConfigSource for &C {
+    fn field<T: DeserializeOwned>(&self, name: &str) -> T {
+        (**self).field(name)
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use anymap::any::CloneAny;
+
+use crate::{Container, TypeInfo};
+
+/// Chooses the stored values a [FrozenContainer] keeps, created with [Container::freeze].
+///
+/// A Container can't list its values as `Send + Sync`, so each stored type is named with
+/// [FrozenContainerBuilder::keep], or [FrozenContainerBuilder::discard] if it can't be shared
+/// between threads.
+pub struct FrozenContainerBuilder<I = ()> {
+    container: Container<I>,
+    kept: anymap::Map<dyn CloneAny + Send + Sync>,
+    named: Vec<TypeInfo>,
+}
+
+impl<I> FrozenContainerBuilder<I> {
+    pub(crate) fn new(container: Container<I>) -> FrozenContainerBuilder<I> {
+        FrozenContainerBuilder {
+            container,
+            kept: anymap::Map::new(),
+            named: Vec::new(),
+        }
+    }
+
+    /// Keep the stored T, if there is one, including one stored in the [Container::overlay]
+    /// parent.
+    pub fn keep<T: Send + Sync + 'static>(mut self) -> Self {
+        if let Some(got) = self.container.get_cached::<T>() {
+            self.kept.insert(got);
+        }
+        self.named.push(TypeInfo::of::<T>());
+        self
+    }
+
+    /// Drop the stored T, if there is one, instead of keeping it.
+    pub fn discard<T: ?Sized + 'static>(mut self) -> Self {
+        self.named.push(TypeInfo::of::<T>());
+        self
+    }
+
+    /// Construct the FrozenContainer, dropping the Container and anything discarded.
+    ///
+    /// Panics if a value stored in the Container wasn't kept or discarded. Values in the
+    /// [Container::overlay] parent aren't checked.
+    pub fn build(mut self) -> FrozenContainer<I> {
+        let missing: Vec<_> = self
+            .container
+            .built_types
+            .iter()
+            .filter(|t| !self.named.contains(t))
+            .collect();
+        if !missing.is_empty() {
+            panic!("stored values weren't kept or discarded: {missing:?}");
+        }
+
+        FrozenContainer {
+            input: self.container.take_input(),
+            built: self.kept,
+        }
+    }
+}
+
+/// A read-only container that only resolves values already stored, and can be shared between
+/// threads.
+///
+/// Resolving takes `&self`, never builds and doesn't lock.
+pub struct FrozenContainer<I = ()> {
+    input: Option<I>,
+    built: anymap::Map<dyn CloneAny + Send + Sync>,
+}
+
+impl<I> FrozenContainer<I> {
+    /// Get a reference to the provided input.
+    ///
+    /// Panics if the input was taken with [Container::take_input] before freezing.
+    pub fn input(&self) -> &I {
+        self.input.as_ref().expect("input was taken")
+    }
+
+    /// Get the kept T, or None if it wasn't stored or kept.
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.built.get::<Arc<T>>().map(Arc::clone)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::Build;
+
+    struct Pool(u8);
+
+    impl Build for Pool {
+        fn build(_: &mut Container) -> Self {
+            Pool(7)
+        }
+    }
+
+    #[test]
+    fn gets_kept_from_threads() {
+        let mut c = Container::new(());
+        let pool = c.get::<Pool>();
+        c.insert(String::from("discarded"));
+
+        let frozen = c
+            .freeze()
+            .keep::<Pool>()
+            .keep::<u32>()
+            .discard::<String>()
+            .build();
+        thread::scope(|s| {
+            for _ in 0..2 {
+                s.spawn(|| {
+                    assert!(Arc::ptr_eq(&frozen.get::<Pool>().unwrap(), &pool));
+                    assert_eq!(frozen.get::<Pool>().unwrap().0, 7);
+                    assert!(frozen.get::<String>().is_none());
+                    assert!(frozen.get::<u32>().is_none());
+                });
+            }
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Pool")]
+    fn panics_on_values_not_kept() {
+        let mut c = Container::new(());
+        c.get::<Pool>();
+
+        c.freeze().build();
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    cell::{OnceCell, RefCell},
+    ops::Deref,
+    sync::Arc,
+};
+
+use crate::{Build, Container};
+
+/// A T that is only built the first time it is used.
+///
+/// Building a Lazy takes a [Container::scope] of the Container, so T shares the values stored at
+/// that point. Values stored afterwards are not visible to T, and anything built for T is stored
+/// only in the Lazy's scope.
+pub struct Lazy<T, I = ()> {
+    value: OnceCell<Arc<T>>,
+    container: RefCell<Option<Container<I>>>,
+}
+
+impl<T: Build<I>, I: Clone + 'static> Lazy<T, I> {
+    /// Get the T, building it if this is the first use.
+    ///
+    /// If building T panics, the next use tries again.
+    pub fn force(&self) -> &Arc<T> {
+        self.value.get_or_init(|| {
+            let mut container = self.container.borrow_mut();
+            let value = container.as_mut().unwrap().get();
+            // The scope is only needed until T is built.
+            *container = None;
+            value
+        })
+    }
+}
+
+impl<T: Build<I>, I: Clone + 'static> Build<I> for Lazy<T, I> {
+    fn build(container: &mut Container<I>) -> Self {
+        Lazy {
+            value: OnceCell::new(),
+            container: RefCell::new(Some(container.scope())),
+        }
+    }
+}
+
+impl<T: Build<I>, I: Clone + 'static> Deref for Lazy<T, I> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.force()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    use super::*;
+
+    static EXPENSIVE_BUILT: AtomicBool = AtomicBool::new(false);
+
+    struct Expensive;
+
+    impl Build for Expensive {
+        fn build(_: &mut Container) -> Self {
+            EXPENSIVE_BUILT.store(true, Ordering::SeqCst);
+            Expensive
+        }
+    }
+
+    struct Owner {
+        expensive: Arc<Lazy<Expensive>>,
+    }
+
+    impl Build for Owner {
+        fn build(constructor: &mut Container) -> Self {
+            Owner {
+                expensive: constructor.get(),
+            }
+        }
+    }
+
+    #[test]
+    fn builds_on_force() {
+        let mut c = Container::new(());
+
+        let owner: Arc<Owner> = c.get();
+        assert!(!EXPENSIVE_BUILT.load(Ordering::SeqCst));
+
+        let first = Arc::clone(owner.expensive.force());
+        assert!(EXPENSIVE_BUILT.load(Ordering::SeqCst));
+        assert!(Arc::ptr_eq(&first, owner.expensive.force()));
+    }
+
+    static FLAKY_FAILED: AtomicBool = AtomicBool::new(false);
+
+    struct Flaky;
+
+    impl Build for Flaky {
+        fn build(_: &mut Container) -> Self {
+            if !FLAKY_FAILED.swap(true, Ordering::SeqCst) {
+                panic!("first build fails");
+            }
+            Flaky
+        }
+    }
+
+    #[test]
+    fn retries_force_after_panic() {
+        let mut c = Container::new(());
+
+        let lazy: Lazy<Flaky> = c.build();
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| lazy.force())).is_err());
+        lazy.force();
+    }
+}
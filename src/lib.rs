@@ -32,131 +32,1915 @@
 //!   assert_eq!(Arc::as_ptr(&bar.foo), Arc::as_ptr(&foo));
 //! }
 //! ```
-use std::{any::TypeId, sync::Arc};
+use anymap::any::CloneAny;
+use std::{
+    any::{self, Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    future::{self, Future},
+    panic::{self, AssertUnwindSafe},
+    pin,
+    rc::Rc,
+    sync::{Arc, Mutex, Weak},
+    task::Poll,
+};
 
 pub use forgy_derive::Build;
 
+mod builder;
+pub use builder::ContainerBuilder;
+
+#[cfg(feature = "serde")]
+mod config;
+#[cfg(feature = "serde")]
+pub use config::ConfigSource;
+
+mod frozen;
+pub use frozen::{FrozenContainer, FrozenContainerBuilder};
+
+mod lazy;
+pub use lazy::Lazy;
+
+mod provider;
+pub use provider::Provider;
+
+#[cfg(feature = "registry")]
+mod registry;
+#[cfg(feature = "registry")]
+#[doc(hidden)]
+pub use inventory;
+#[cfg(feature = "registry")]
+pub use {forgy_derive::provides, registry::Registration};
+
+mod sync;
+pub use sync::{BuildSync, SyncContainer};
+
+#[cfg(feature = "test-util")]
+pub mod test_util;
+
+mod weak;
+pub use weak::WeakRef;
+
+/// A type being constructed, with its name for diagnostics.
+#[derive(Clone, Copy)]
+pub struct TypeInfo {
+    pub id: TypeId,
+    pub name: &'static str,
+}
+
+impl TypeInfo {
+    /// The [TypeInfo] of T.
+    pub fn of<T: ?Sized + 'static>() -> TypeInfo {
+        TypeInfo {
+            id: TypeId::of::<T>(),
+            name: any::type_name::<T>(),
+        }
+    }
+}
+
+/// Compares names too, since values built with [Container::build_dyn] are told apart by the name
+/// they were registered as.
+impl PartialEq for TypeInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.name == other.name
+    }
+}
+
+impl Eq for TypeInfo {}
+
+impl fmt::Debug for TypeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+/// Errors that can occur while constructing values in a [Container].
+#[derive(Debug)]
+pub enum ForgyError {
+    /// `ty` was requested while it was already being constructed.
+    Cycle { ty: TypeInfo, stack: Vec<TypeInfo> },
+    /// `ty` was requested while `stack` was already at [Container::set_max_depth].
+    DepthExceeded { ty: TypeInfo, stack: Vec<TypeInfo> },
+}
+
+impl ForgyError {
+    /// Names of the types being constructed, from the root to the requested type. For a cycle,
+    /// the repeated type appears twice.
+    pub fn path(&self) -> Vec<&'static str> {
+        match self {
+            ForgyError::Cycle { ty, stack } | ForgyError::DepthExceeded { ty, stack } => {
+                stack.iter().chain([ty]).map(|t| t.name).collect()
+            }
+        }
+    }
+
+    /// Name of the type that was requested while it was already being constructed, or past the
+    /// maximum depth.
+    pub fn repeated(&self) -> &'static str {
+        match self {
+            ForgyError::Cycle { ty, .. } | ForgyError::DepthExceeded { ty, .. } => ty.name,
+        }
+    }
+
+    /// Index in [ForgyError::path] where the cycle starts, the first appearance of the repeated
+    /// type. Always 0 for [ForgyError::DepthExceeded].
+    pub fn cycle_start(&self) -> usize {
+        match self {
+            ForgyError::Cycle { ty, stack } => stack.iter().position(|t| t == ty).unwrap_or(0),
+            ForgyError::DepthExceeded { .. } => 0,
+        }
+    }
+}
+
+impl fmt::Display for ForgyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ForgyError::Cycle { ty, stack } => {
+                let path = stack.iter().chain([ty]);
+                write!(f, "Cycle constructing {ty:?}: ")?;
+                f.debug_list().entries(path).finish()?;
+                write!(
+                    f,
+                    ". To refer back to {ty:?}, use a WeakRef or Container::get_cyclic"
+                )
+            }
+            ForgyError::DepthExceeded { ty, stack } => {
+                let path = stack.iter().chain([ty]);
+                write!(f, "Max depth exceeded constructing {ty:?}: ")?;
+                f.debug_list().entries(path).finish()
+            }
+        }
+    }
+}
+
+impl std::error::Error for ForgyError {}
+
 /// A type that can be constructed given the [Container].
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be built from a `Container<{I}>`",
+    label = "requires `Build<{I}>`",
+    note = "types derived without `#[forgy(input = ...)]` can be built from any input"
+)]
 pub trait Build<I = ()>: 'static {
+    /// Whether [Container::get] builds a new value every time instead of storing one.
+    ///
+    /// Derived types can opt in with `#[forgy(transient)]`.
+    const TRANSIENT: bool = false;
+
     fn build(container: &mut Container<I>) -> Self;
+
+    /// Called by [Container::get] after storing a newly built value.
+    ///
+    /// Derived types can opt in with `#[forgy(on_built)]`, which calls [OnBuilt::on_built].
+    fn after_build(self: &Arc<Self>, container: &mut Container<I>) {
+        let _ = container;
+    }
+
+    /// Called by [Container::get] and [Container::try_get] before building a T. Returning a value
+    /// stores and uses it instead, without calling [Build::after_build].
+    ///
+    /// Derived types can opt in with `#[forgy(cache_as = ...)]`, to share another type's stored
+    /// value.
+    fn get_shared(container: &mut Container<I>) -> Result<Option<Arc<Self>>, ForgyError> {
+        let _ = container;
+        Ok(None)
+    }
+}
+
+/// Post-construction setup for derived types with `#[forgy(on_built)]`.
+pub trait OnBuilt<I = ()> {
+    /// Called after the value is stored, so getting it from the [Container] returns `self`.
+    fn on_built(self: &Arc<Self>, container: &mut Container<I>);
+}
+
+/// An error from a [TryBuild].
+pub type BuildError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A type whose construction given the [Container] can fail.
+///
+/// Every [Build] type is also a TryBuild that always succeeds.
+pub trait TryBuild<I = ()>: 'static + Sized {
+    fn try_build(container: &mut Container<I>) -> Result<Self, BuildError>;
+
+    /// [Container::get_try], so [Build] types can go through [Container::try_get].
+    #[doc(hidden)]
+    fn get_try(container: &mut Container<I>) -> Result<Arc<Self>, BuildError> {
+        container.get_try_built()
+    }
+}
+
+impl<I, T: Build<I>> TryBuild<I> for T {
+    fn try_build(container: &mut Container<I>) -> Result<Self, BuildError> {
+        Ok(T::build(container))
+    }
+
+    fn get_try(container: &mut Container<I>) -> Result<Arc<Self>, BuildError> {
+        Ok(container.try_get()?)
+    }
+}
+
+/// Builds the T and wraps it, so an `Arc<Mutex<T>>` dependency is a shared mutable singleton.
+impl<I, T: Build<I>> Build<I> for Mutex<T> {
+    fn build(container: &mut Container<I>) -> Self {
+        Mutex::new(container.build())
+    }
+}
+
+/// A type that can be asynchronously constructed given the [Container].
+#[allow(async_fn_in_trait)]
+pub trait BuildAsync<I = ()>: 'static {
+    async fn build_async(container: &mut Container<I>) -> Self;
 }
 
+/// Counts of [Container::get] calls, from [Container::stats].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ContainerStats {
+    /// Calls that returned an already stored value.
+    pub hits: usize,
+    /// Calls that built or shared a new value.
+    pub misses: usize,
+}
+
+type Wrapper = Rc<dyn Fn(&'static str, &mut dyn FnMut())>;
+type Factory<I> = Box<dyn FnOnce(&mut Container<I>) -> Box<dyn Any>>;
+type Resolver<I> = Rc<dyn Fn(&mut Container<I>) -> Box<dyn Any>>;
+
 /// A container for constructed objects.
+///
+/// The input may be borrowed, as in `Container<&Config>`. Constructed objects are `'static`, so
+/// they can copy from a borrowed input but not borrow from it, and [Lazy] needs an owned input.
 pub struct Container<I = ()> {
-    input: I,
-    built: anymap::AnyMap,
+    input: Option<I>,
+    built: anymap::Map<dyn CloneAny>,
+    built_types: Vec<TypeInfo>,
+    factories: HashMap<TypeId, Factory<I>>,
+    many: HashMap<TypeId, Vec<Resolver<I>>>,
+    bindings: HashMap<TypeId, Resolver<I>>,
+    dynamic: HashMap<String, (TypeInfo, Resolver<I>)>,
+    named: HashMap<(TypeId, String), Rc<dyn Any>>,
+    on_build: Option<Rc<dyn Fn(&'static str)>>,
+    wrapper: Option<Wrapper>,
+    max_depth: Option<usize>,
+    stats: ContainerStats,
+    weak: HashMap<TypeId, Vec<Box<dyn Any>>>,
+    #[cfg(feature = "metrics")]
+    timings: Vec<(TypeInfo, std::time::Duration)>,
+
+    parent: Option<Rc<Container<I>>>,
 
-    stack: Vec<TypeId>,
+    stack: Rc<RefCell<Vec<TypeInfo>>>,
 }
 
 impl<I> Container<I> {
     /// Construct a new Container with the provided input.
     pub fn new(input: I) -> Container<I> {
         Container {
-            input,
-            built: anymap::AnyMap::new(),
-            stack: Vec::new(),
+            input: Some(input),
+            built: anymap::Map::new(),
+            built_types: Vec::new(),
+            factories: HashMap::new(),
+            many: HashMap::new(),
+            bindings: HashMap::new(),
+            dynamic: HashMap::new(),
+            named: HashMap::new(),
+            on_build: None,
+            wrapper: None,
+            max_depth: None,
+            stats: ContainerStats::default(),
+            weak: HashMap::new(),
+            #[cfg(feature = "metrics")]
+            timings: Vec::new(),
+            parent: None,
+            stack: Rc::default(),
+        }
+    }
+
+    /// Construct a new Container with the provided input that reads values stored in `parent`.
+    ///
+    /// Every lookup, like [Container::get], uses a T stored in this Container, then one stored in
+    /// `parent`, and only then builds one. Values built by the overlay are stored only in the
+    /// overlay.
+    ///
+    /// `parent` is an [Rc] rather than an [Arc] since a Container can't be shared between threads
+    /// anyway, use a [SyncContainer] for that.
+    pub fn overlay(parent: Rc<Container<I>>, input: I) -> Container<I> {
+        Container {
+            parent: Some(parent),
+            ..Container::new(input)
         }
     }
 
+    /// Construct a new Container with the input converted from `input`, as in
+    /// `Container::<String>::from("config")`.
+    pub fn from(input: impl Into<I>) -> Container<I> {
+        Container::new(input.into())
+    }
+
+    /// Construct a new Container with the provided input, the same as [Container::new].
+    pub fn with_input(input: I) -> Container<I> {
+        Container::new(input)
+    }
+
     /// Get a reference to the provided input.
+    ///
+    /// Panics if the input was taken with [Container::take_input].
     pub fn input(&self) -> &I {
-        &self.input
+        self.input.as_ref().expect("input was taken")
+    }
+
+    /// Get a mutable reference to the provided input.
+    ///
+    /// Panics if the input was taken with [Container::take_input].
+    pub fn input_mut(&mut self) -> &mut I {
+        self.input.as_mut().expect("input was taken")
+    }
+
+    /// Get the stored `Arc<I>`, or store a clone of the input, so the input can be shared like a
+    /// built value.
+    ///
+    /// The stored clone is kept by [Container::replace_input].
+    ///
+    /// Panics if the input was taken with [Container::take_input].
+    pub fn input_arc(&mut self) -> Arc<I>
+    where
+        I: Clone + 'static,
+    {
+        self.get_or_build_with(|c| c.input().clone())
+    }
+
+    /// Take the input, leaving stored values available.
+    ///
+    /// Returns `None` if the input was already taken.
+    pub fn take_input(&mut self) -> Option<I> {
+        self.input.take()
+    }
+
+    /// Replace the input, returning the previous one.
+    ///
+    /// Stored values built from the previous input are kept, use [Container::clear] or
+    /// [Container::remove] to rebuild them from the new one.
+    ///
+    /// Panics if the input was taken with [Container::take_input].
+    pub fn replace_input(&mut self, input: I) -> I {
+        std::mem::replace(self.input_mut(), input)
+    }
+
+    /// Whether a T has already been created and stored, here or in the [Container::overlay]
+    /// parent.
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.get_cached::<T>().is_some()
+    }
+
+    /// Counts of [Container::get] and [Container::try_get] calls on this Container, including
+    /// those made while building. Scopes start their own counts.
+    pub fn stats(&self) -> ContainerStats {
+        self.stats
+    }
+
+    /// Number of stored values, not counting named values.
+    pub fn prebuilt_count(&self) -> usize {
+        self.built_types.len()
+    }
+
+    /// Get the already created T, without building one.
+    ///
+    /// Includes values stored in the [Container::overlay] parent.
+    pub fn get_cached<T: ?Sized + 'static>(&self) -> Option<Arc<T>> {
+        self.stored().cloned()
+    }
+
+    /// The stored T, reading through to the [Container::overlay] parent without storing it here.
+    fn stored<T: ?Sized + 'static>(&self) -> Option<&Arc<T>> {
+        match self.built.get::<Arc<T>>() {
+            Some(got) => Some(got),
+            None => self.parent.as_ref()?.stored(),
+        }
+    }
+
+    /// Store a T, replacing any existing one, without going through [Build].
+    pub fn insert<T: 'static>(&mut self, value: T) -> Arc<T> {
+        self.insert_arc(Arc::new(value))
+    }
+
+    /// Store an already shared T, replacing any existing one.
+    pub fn insert_arc<T: 'static>(&mut self, value: Arc<T>) -> Arc<T> {
+        self.store(&value);
+        value
+    }
+
+    /// Run `f` with `value` stored as the T.
+    ///
+    /// Afterwards, even if `f` panics, the stored values are restored to what they were before,
+    /// so nothing built with the override outlives `f`.
+    pub fn with_override<T: 'static, R>(&mut self, value: T, f: impl FnOnce(&mut Self) -> R) -> R {
+        let snapshot = (self.built.clone(), self.built_types.clone());
+        self.insert(value);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(self)));
+        (self.built, self.built_types) = snapshot;
+
+        match result {
+            Ok(r) => r,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+
+    /// Remove and return the stored T, so the next [Container::get] builds a new one.
+    ///
+    /// A T stored in the [Container::overlay] parent isn't removed, and is still used.
+    pub fn remove<T: 'static>(&mut self) -> Option<Arc<T>> {
+        let ty = TypeInfo::of::<T>();
+        self.built_types.retain(|t| *t != ty);
+        self.built.remove::<Arc<T>>()
+    }
+
+    /// Use `f` instead of [Build] the next time a T is built.
+    ///
+    /// Factories are not shared with [Container::scope]s.
+    pub fn register<T: 'static>(&mut self, f: impl FnOnce(&mut Container<I>) -> T + 'static) {
+        self.factories.insert(
+            TypeId::of::<T>(),
+            Box::new(|container| Box::new(f(container))),
+        );
+    }
+
+    /// Add C to the implementations of T returned by [Container::get_all].
+    ///
+    /// `upcast` converts to T, usually just `|c| c` for a trait object T.
+    pub fn register_many<T: ?Sized + 'static, C: Build<I>>(
+        &mut self,
+        upcast: fn(Arc<C>) -> Arc<T>,
+    ) {
+        let resolver: Resolver<I> =
+            Rc::new(move |container| Box::new(upcast(container.get::<C>())));
+        self.many
+            .entry(TypeId::of::<T>())
+            .or_default()
+            .push(resolver);
+    }
+
+    /// Use C as the implementation of T returned by [Container::get_bound].
+    ///
+    /// `upcast` converts to T, usually just `|c| c` for a trait object T.
+    pub fn bind<T: ?Sized + 'static, C: Build<I>>(&mut self, upcast: fn(Arc<C>) -> Arc<T>) {
+        let resolver: Resolver<I> =
+            Rc::new(move |container| Box::new(upcast(container.get::<C>())));
+        self.bindings.insert(TypeId::of::<T>(), resolver);
+    }
+
+    /// Use `f` to build a value for [Container::build_dyn] with `name`, for types chosen at runtime.
+    ///
+    /// Each distinct `name` is kept for the rest of the program, to report it in errors.
+    pub fn register_dyn<T: 'static>(
+        &mut self,
+        name: &str,
+        f: impl Fn(&mut Container<I>) -> T + 'static,
+    ) {
+        let name = match self.dynamic.get(name) {
+            Some((ty, _)) => ty.name,
+            None => String::leak(name.to_string()),
+        };
+        let ty = TypeInfo {
+            id: TypeId::of::<T>(),
+            name,
+        };
+        let resolver: Resolver<I> = Rc::new(move |container| Box::new(f(container)));
+        self.dynamic.insert(name.to_string(), (ty, resolver));
+    }
+
+    /// Build and do not store a new value with the factory registered as `name` with
+    /// [Container::register_dyn], or `None` if there isn't one.
+    ///
+    /// Panics if constructing the value encounters a cycle.
+    pub fn build_dyn(&mut self, name: &str) -> Option<Box<dyn Any>> {
+        let (ty, resolve) = self.dynamic.get(name).cloned()?;
+        match self.construct(ty, |container| resolve(container)) {
+            Ok(new) => Some(new),
+            Err(e) => self.raise(e),
+        }
+    }
+
+    /// Build and do not store a new T, type erased.
+    ///
+    /// Panics if constructing T encounters a cycle.
+    pub fn build_boxed<T: Build<I>>(&mut self) -> Box<dyn Any> {
+        Box::new(self.build::<T>())
+    }
+
+    /// Get the already stored T, or get the implementation added with [Container::bind] and store
+    /// it as the T.
+    ///
+    /// Panics if no implementation of T was bound.
+    pub fn get_bound<T: ?Sized + 'static>(&mut self) -> Arc<T> {
+        if let Some(got) = self.get_cached() {
+            return got;
+        }
+
+        let Some(resolve) = self.bindings.get(&TypeId::of::<T>()).cloned() else {
+            panic!("no implementation bound for {}", any::type_name::<T>());
+        };
+        let new = *resolve(self).downcast::<Arc<T>>().unwrap();
+        self.store(&new);
+        new
+    }
+
+    /// Get every implementation of T added with [Container::register_many], in the order they
+    /// were added.
+    ///
+    /// Implementations added with `provides` are matched to the Container through [Any], which
+    /// needs a `'static` input, so they're only included by `get_provided` with the `registry`
+    /// feature. Leaving them out here keeps `get_all` usable with borrowed inputs.
+    pub fn get_all<T: ?Sized + 'static>(&mut self) -> Vec<Arc<T>> {
+        let resolvers = self
+            .many
+            .get(&TypeId::of::<T>())
+            .cloned()
+            .unwrap_or_default();
+        resolvers
+            .iter()
+            .map(|resolve| *resolve(self).downcast::<Arc<T>>().unwrap())
+            .collect()
+    }
+
+    /// Call `f` with the type name every time a value is built.
+    pub fn set_on_build(&mut self, f: impl Fn(&'static str) + 'static) {
+        self.on_build = Some(Rc::new(f));
+    }
+
+    /// Build every value inside `f`, which gets the type name and must call the build exactly
+    /// once, such as to enter a tracing span around it.
+    ///
+    /// The build returns to `f` even if it fails, so `f` can clean up.
+    pub fn set_wrapper(&mut self, f: impl Fn(&'static str, &mut dyn FnMut()) + 'static) {
+        self.wrapper = Some(Rc::new(f));
+    }
+
+    /// Fail construction with [ForgyError::DepthExceeded] once `max` types are being constructed at
+    /// once, rather than overflowing the stack. Unlimited by default.
+    pub fn set_max_depth(&mut self, max: usize) {
+        self.max_depth = Some(max);
+    }
+
+    /// Number of types currently being constructed, counting the one calling this.
+    pub fn depth(&self) -> usize {
+        self.stack.borrow().len()
+    }
+
+    /// Stop building, keeping the stored values chosen with [FrozenContainerBuilder::keep] for
+    /// `&self` reads from any thread. Every stored value must be kept or discarded.
+    pub fn freeze(self) -> FrozenContainerBuilder<I> {
+        FrozenContainerBuilder::new(self)
+    }
+
+    /// Create a child Container with a clone of the input.
+    ///
+    /// Values already stored in this Container are shared with the child. Anything the child
+    /// builds is stored only in the child and dropped with it. Values this Container stores after
+    /// the child is created are not visible to the child.
+    pub fn scope(&self) -> Container<I>
+    where
+        I: Clone,
+    {
+        Container {
+            input: self.input.clone(),
+            built: self.built.clone(),
+            built_types: self.built_types.clone(),
+            factories: HashMap::new(),
+            many: self.many.clone(),
+            bindings: self.bindings.clone(),
+            dynamic: self.dynamic.clone(),
+            named: self.named.clone(),
+            on_build: self.on_build.clone(),
+            wrapper: self.wrapper.clone(),
+            max_depth: self.max_depth,
+            stats: ContainerStats::default(),
+            weak: HashMap::new(),
+            #[cfg(feature = "metrics")]
+            timings: Vec::new(),
+            parent: self.parent.clone(),
+            stack: Rc::default(),
+        }
+    }
+
+    /// Create a new Container with the input projected by `f`.
+    ///
+    /// The new Container starts empty. Nothing stored in this Container is shared with it, since
+    /// values built from one input aren't necessarily valid for another.
+    ///
+    /// Panics if the input was taken with [Container::take_input].
+    pub fn map_input<J>(&self, f: impl FnOnce(&I) -> J) -> Container<J> {
+        Container::new(f(self.input()))
+    }
+
+    /// Drop every stored value, keeping the input.
+    pub fn clear(&mut self) {
+        self.built.clear();
+        self.built_types.clear();
+        self.named.clear();
+    }
+
+    /// Get the already created T, or build and store a new T.
+    ///
+    /// A [Build::TRANSIENT] T is built every time and never stored.
+    ///
+    /// Panics if constructing T encounters a cycle.
+    pub fn get<T: Build<I>>(&mut self) -> Arc<T> {
+        if T::TRANSIENT {
+            self.stats.misses += 1;
+            return Arc::new(self.build());
+        }
+        if let Some(got) = self.get_cached() {
+            self.stats.hits += 1;
+            return got;
+        }
+        self.stats.misses += 1;
+
+        match T::get_shared(self) {
+            Ok(Some(shared)) => {
+                self.store(&shared);
+                return shared;
+            }
+            Ok(None) => {}
+            Err(e) => self.raise(e),
+        }
+
+        // Building needs the whole Container, so an entry can't be held across it and the miss
+        // path looks T up again to store it.
+        let new = Arc::new(self.build());
+        self.store(&new);
+        T::after_build(&new, self);
+        new
+    }
+
+    /// [Container::get] a T now rather than on first use, returning the Container for chaining.
+    ///
+    /// Panics if constructing T encounters a cycle.
+    pub fn ensure<T: Build<I>>(&mut self) -> &mut Self {
+        self.get::<T>();
+        self
+    }
+
+    /// [Container::ensure] a T, taking and returning the Container for fluent setup.
+    ///
+    /// Panics if constructing T encounters a cycle.
+    pub fn prebuild<T: Build<I>>(mut self) -> Self {
+        self.ensure::<T>();
+        self
+    }
+
+    /// [Container::get] an A and a B.
+    pub fn get2<A: Build<I>, B: Build<I>>(&mut self) -> (Arc<A>, Arc<B>) {
+        (self.get(), self.get())
+    }
+
+    /// [Container::get] an A, a B and a C.
+    pub fn get3<A: Build<I>, B: Build<I>, C: Build<I>>(&mut self) -> (Arc<A>, Arc<B>, Arc<C>) {
+        (self.get(), self.get(), self.get())
+    }
+
+    /// Get a reference to the already created T, or build and store a new T.
+    ///
+    /// Panics if constructing T encounters a cycle, or if T is [Build::TRANSIENT].
+    pub fn get_ref<T: Build<I>>(&mut self) -> &T {
+        if T::TRANSIENT {
+            panic!("{} is transient and never stored", any::type_name::<T>());
+        }
+        if self.stored::<T>().is_none() {
+            self.get::<T>();
+        }
+        self.stored::<T>().unwrap()
+    }
+
+    /// Get a clone of the already created T, or build and store a new T and clone it.
+    ///
+    /// Panics if constructing T encounters a cycle.
+    pub fn get_cloned<T: Build<I> + Clone>(&mut self) -> T {
+        T::clone(&self.get())
+    }
+
+    /// Get a [Weak] to the already created T, or build and store a new T.
+    ///
+    /// The Weak upgrades as long as the Container stores the T.
+    ///
+    /// Panics if constructing T encounters a cycle, or if T is [Build::TRANSIENT].
+    pub fn get_weak<T: Build<I>>(&mut self) -> Weak<T> {
+        if T::TRANSIENT {
+            panic!("{} is transient and never stored", any::type_name::<T>());
+        }
+        Arc::downgrade(&self.get())
+    }
+
+    /// Get the already created T, or store a new T from `f`.
+    pub fn get_or_build_with<T: 'static>(&mut self, f: impl FnOnce(&mut Self) -> T) -> Arc<T> {
+        if let Some(got) = self.get_cached() {
+            return got;
+        }
+
+        let new = Arc::new(f(self));
+        self.store(&new);
+        new
+    }
+
+    /// Get the already created T, or store a new `T::default()`, for types that aren't [Build].
+    pub fn get_or_default<T: Default + 'static>(&mut self) -> Arc<T> {
+        self.get_or_build_with(|_| T::default())
+    }
+
+    /// Fill `slot` with [Container::get] if it is empty, otherwise do nothing.
+    ///
+    /// Panics if constructing T encounters a cycle.
+    pub fn build_into<T: Build<I>>(&mut self, slot: &mut Option<Arc<T>>) {
+        if slot.is_none() {
+            *slot = Some(self.get());
+        }
+    }
+
+    /// Get the already created T, or store a new T from `f`, which gets a [Weak] reference to the
+    /// T it's constructing, as with [Arc::new_cyclic].
+    ///
+    /// The Weak can't be upgraded until `f` returns.
+    pub fn get_cyclic<T: 'static>(&mut self, f: impl FnOnce(&Weak<T>, &mut Self) -> T) -> Arc<T> {
+        if let Some(got) = self.get_cached() {
+            return got;
+        }
+
+        let new = Arc::new_cyclic(|weak| f(weak, self));
+        self.store(&new);
+        new
+    }
+
+    /// Get the already created T, or try to build and store a new T.
+    ///
+    /// Errors from T's dependencies, built by [Build::build] with [Container::get], unwind to this
+    /// call to be returned. With `panic = "abort"`, they abort the process instead.
+    pub fn try_get<T: Build<I>>(&mut self) -> Result<Arc<T>, ForgyError> {
+        if T::TRANSIENT {
+            self.stats.misses += 1;
+            return self.try_build().map(Arc::new);
+        }
+        if let Some(got) = self.get_cached() {
+            self.stats.hits += 1;
+            return Ok(got);
+        }
+        self.stats.misses += 1;
+
+        if let Some(shared) = T::get_shared(self)? {
+            self.store(&shared);
+            return Ok(shared);
+        }
+
+        let new = Arc::new(self.try_build()?);
+        self.store(&new);
+        T::after_build(&new, self);
+        Ok(new)
+    }
+
+    /// Build and do not store a new T.
+    ///
+    /// Panics if constructing T encounters a cycle.
+    pub fn build<T: Build<I>>(&mut self) -> T {
+        match self.try_build() {
+            Ok(new) => new,
+            Err(e) => self.raise(e),
+        }
+    }
+
+    /// Build a T from a new Container with `input`, for a T built from a different input than this
+    /// Container's.
+    ///
+    /// The new Container starts empty and is dropped afterwards, so nothing is stored in either.
+    /// It uses this Container's [Container::set_on_build], [Container::set_wrapper] and
+    /// [Container::set_max_depth], and continues this Container's build, so cycles and depth are
+    /// checked across both.
+    ///
+    /// Panics if constructing T encounters a cycle.
+    pub fn build_with_input<J, T: Build<J>>(&self, input: J) -> T {
+        match self.try_build_with_input(input) {
+            Ok(new) => new,
+            Err(e) => self.raise(e),
+        }
+    }
+
+    /// Try to build a T from a new Container with `input`, as with [Container::build_with_input].
+    pub fn try_build_with_input<J, T: Build<J>>(&self, input: J) -> Result<T, ForgyError> {
+        let mut sub = Container::new(input);
+        sub.on_build = self.on_build.clone();
+        sub.wrapper = self.wrapper.clone();
+        sub.max_depth = self.max_depth;
+        sub.stack = Rc::clone(&self.stack);
+        sub.try_build()
+    }
+
+    /// Build and do not store `n` new Ts, such as for a pool of workers.
+    ///
+    /// Panics if constructing T encounters a cycle.
+    pub fn build_n<T: Build<I>>(&mut self, n: usize) -> Vec<T> {
+        (0..n).map(|_| self.build()).collect()
+    }
+
+    /// Get the already created T, or build and store one with the factory from
+    /// [Container::register]. Without either, returns `None` instead of building a T.
+    ///
+    /// Panics if constructing T encounters a cycle.
+    pub fn get_optional<T: 'static>(&mut self) -> Option<Arc<T>> {
+        if let Some(got) = self.get_cached() {
+            return Some(got);
+        }
+
+        let factory = self.factories.remove(&TypeId::of::<T>())?;
+        let new = match self.construct(TypeInfo::of::<T>(), factory) {
+            Ok(new) => Arc::new(*new.downcast::<T>().unwrap()),
+            Err(e) => self.raise(e),
+        };
+        self.store(&new);
+        Some(new)
+    }
+
+    /// Get the T stored as `name`, or build and store a new T as `name`.
+    ///
+    /// Named values are separate from the T stored by [Container::get].
+    pub fn get_named<T: Build<I>>(&mut self, name: &str) -> Arc<T> {
+        self.get_named_or_build_with(name, |container| container.build())
+    }
+
+    /// Get the T stored as `name`, or store a new T from `f` as `name`.
+    pub fn get_named_or_build_with<T: 'static>(
+        &mut self,
+        name: &str,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> Arc<T> {
+        let key = (TypeId::of::<T>(), name.to_string());
+        if let Some(got) = self.named.get(&key) {
+            return Arc::clone(got.downcast_ref().unwrap());
+        }
+
+        let new = Arc::new(f(self));
+        self.named.insert(key, Rc::new(Arc::clone(&new)));
+        new
+    }
+
+    /// Store a T as `name`, replacing any existing one.
+    pub fn insert_named<T: 'static>(&mut self, name: &str, value: T) -> Arc<T> {
+        let new = Arc::new(value);
+        self.named.insert(
+            (TypeId::of::<T>(), name.to_string()),
+            Rc::new(Arc::clone(&new)),
+        );
+        new
+    }
+
+    /// Build a new T even if one is already stored, without storing it.
+    ///
+    /// Dependencies of T are still taken from stored values. This is the same as
+    /// [Container::build], which never reads a stored T.
+    pub fn build_fresh<T: Build<I>>(&mut self) -> T {
+        self.build()
+    }
+
+    /// Get the already created T, or asynchronously build and store a new T.
+    ///
+    /// Panics if constructing T encounters a cycle.
+    pub async fn get_async<T: BuildAsync<I>>(&mut self) -> Arc<T> {
+        if let Some(got) = self.get_cached() {
+            return got;
+        }
+
+        let new = Arc::new(self.build_async().await);
+        self.store(&new);
+        new
+    }
+
+    /// Asynchronously build and do not store a new T.
+    ///
+    /// Panics if constructing T encounters a cycle.
+    pub async fn build_async<T: BuildAsync<I>>(&mut self) -> T {
+        let guard = match self.enter(TypeInfo::of::<T>()) {
+            Ok(guard) => guard,
+            Err(e) => self.raise(e),
+        };
+        let result = {
+            // Catch errors raised by builds under this one, like `construct` does.
+            let mut build = pin::pin!(T::build_async(self));
+            future::poll_fn(|cx| {
+                match panic::catch_unwind(AssertUnwindSafe(|| build.as_mut().poll(cx))) {
+                    Ok(Poll::Ready(new)) => Poll::Ready(Ok(new)),
+                    Ok(Poll::Pending) => Poll::Pending,
+                    Err(payload) => Poll::Ready(Err(payload)),
+                }
+            })
+            .await
+        };
+        drop(guard);
+
+        match result {
+            Ok(new) => new,
+            Err(payload) => match payload.downcast::<ForgyError>() {
+                Ok(e) => self.raise(*e),
+                Err(payload) => panic::resume_unwind(payload),
+            },
+        }
+    }
+
+    /// Names of the types of stored values, in the order they were first stored.
+    ///
+    /// Named values are not included.
+    pub fn built_type_names(&self) -> Vec<&'static str> {
+        self.built_types.iter().map(|t| t.name).collect()
+    }
+
+    /// Call `f` with the name of the type of each stored value, last stored first.
+    ///
+    /// Dependencies are stored before their dependents, so this is a safe order for teardown.
+    pub fn for_each_built_rev(&self, f: impl FnMut(&'static str)) {
+        self.built_types.iter().rev().map(|t| t.name).for_each(f);
+    }
+
+    /// Total time spent constructing each type, in the order they first finished constructing, so
+    /// dependencies come before their dependents.
+    ///
+    /// A type's time includes constructing the dependencies it built.
+    #[cfg(feature = "metrics")]
+    pub fn build_timings(&self) -> Vec<(&'static str, std::time::Duration)> {
+        self.timings.iter().map(|(t, d)| (t.name, *d)).collect()
+    }
+
+    fn store<T: ?Sized + 'static>(&mut self, value: &Arc<T>) {
+        if self.built.insert(Arc::clone(value)).is_none() {
+            self.built_types.push(TypeInfo::of::<T>());
+        }
+        weak::resolve(self, value);
+    }
+
+    /// Try to build and do not store a new T.
+    ///
+    /// Errors from T's dependencies, built by [Build::build] with [Container::get], unwind to this
+    /// call to be returned. With `panic = "abort"`, they abort the process instead.
+    pub fn try_build<T: Build<I>>(&mut self) -> Result<T, ForgyError> {
+        self.construct(TypeInfo::of::<T>(), |container| {
+            match container.factories.remove(&TypeId::of::<T>()) {
+                Some(factory) => *factory(container).downcast::<T>().unwrap(),
+                None => T::build(container),
+            }
+        })
+    }
+
+    /// Get the already created T, or build and store a new T if its [TryBuild] succeeds.
+    ///
+    /// A [Build] T is resolved with [Container::try_get].
+    pub fn get_try<T: TryBuild<I>>(&mut self) -> Result<Arc<T>, BuildError> {
+        T::get_try(self)
+    }
+
+    fn get_try_built<T: TryBuild<I>>(&mut self) -> Result<Arc<T>, BuildError> {
+        if let Some(got) = self.get_cached() {
+            return Ok(got);
+        }
+
+        let new = Arc::new(self.construct(TypeInfo::of::<T>(), T::try_build)??);
+        self.store(&new);
+        Ok(new)
+    }
+
+    /// Panic with `e`, unwinding to the enclosing build if there is one so it can report the error.
+    ///
+    /// Requires `panic = "unwind"`, since [Build::build] can't return the error.
+    fn raise(&self, e: ForgyError) -> ! {
+        if self.stack.borrow().is_empty() {
+            panic!("{e}");
+        }
+        panic::resume_unwind(Box::new(e))
+    }
+
+    /// Push `ty` onto the stack until the returned guard is dropped.
+    fn enter(&self, ty: TypeInfo) -> Result<StackGuard, ForgyError> {
+        let mut stack = self.stack.borrow_mut();
+        if stack.contains(&ty) {
+            return Err(ForgyError::Cycle {
+                ty,
+                stack: stack.clone(),
+            });
+        }
+        if self.max_depth.is_some_and(|max| stack.len() >= max) {
+            return Err(ForgyError::DepthExceeded {
+                ty,
+                stack: stack.clone(),
+            });
+        }
+
+        if let Some(on_build) = &self.on_build {
+            on_build(ty.name);
+        }
+
+        stack.push(ty);
+        Ok(StackGuard {
+            stack: Rc::clone(&self.stack),
+            depth: stack.len() - 1,
+        })
+    }
+
+    /// Run `f` to construct `ty`, tracking it for cycle detection.
+    fn construct<T>(
+        &mut self,
+        ty: TypeInfo,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> Result<T, ForgyError> {
+        let guard = self.enter(ty)?;
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result = match self.wrapper.clone() {
+            Some(wrapper) => {
+                let mut f = Some(f);
+                let mut result = None;
+                wrapper(ty.name, &mut || {
+                    let f = f
+                        .take()
+                        .expect("build wrapper called the build more than once");
+                    result = Some(panic::catch_unwind(AssertUnwindSafe(|| f(self))));
+                });
+                result.expect("build wrapper didn't call the build")
+            }
+            None => panic::catch_unwind(AssertUnwindSafe(|| f(self))),
+        };
+        drop(guard);
+
+        #[cfg(feature = "metrics")]
+        match self.timings.iter_mut().find(|(t, _)| *t == ty) {
+            Some((_, total)) => *total += start.elapsed(),
+            None => self.timings.push((ty, start.elapsed())),
+        }
+
+        match result {
+            Ok(new) => Ok(new),
+            Err(payload) => match payload.downcast::<ForgyError>() {
+                Ok(e) => Err(*e),
+                Err(payload) => panic::resume_unwind(payload),
+            },
+        }
+    }
+}
+
+/// Removes a type from the stack when its construction ends, even by unwinding or cancellation.
+struct StackGuard {
+    stack: Rc<RefCell<Vec<TypeInfo>>>,
+    depth: usize,
+}
+
+impl Drop for StackGuard {
+    fn drop(&mut self) {
+        self.stack.borrow_mut().truncate(self.depth);
+    }
+}
+
+/// Clones share the stored values, like [Container::scope].
+impl<I: Clone> Clone for Container<I> {
+    fn clone(&self) -> Self {
+        self.scope()
+    }
+}
+
+/// A Container with the default input.
+impl<I: Default> Default for Container<I> {
+    fn default() -> Self {
+        Container::new(I::default())
+    }
+}
+
+/// Shows the input and the names of the types of stored values, since the values are type erased.
+impl<I: fmt::Debug> fmt::Debug for Container<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("Container");
+        match &self.input {
+            Some(input) => s.field("input", input),
+            None => s.field("input", &format_args!("<taken>")),
+        };
+        s.field("built", &self.built_types).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        sync::atomic::{AtomicBool, AtomicU8, Ordering},
+    };
+
+    use super::*;
+
+    struct Unit;
+
+    impl Build for Unit {
+        fn build(_: &mut Container) -> Self {
+            Unit
+        }
+    }
+
+    struct Counter(u8);
+
+    impl Build for Counter {
+        fn build(_: &mut Container) -> Self {
+            static CONSTRUCTED: AtomicU8 = AtomicU8::new(0);
+            Counter(CONSTRUCTED.fetch_add(1, std::sync::atomic::Ordering::SeqCst))
+        }
+    }
+
+    struct HasDep {
+        #[expect(unused)]
+        unit: Arc<Unit>,
+    }
+
+    impl Build for HasDep {
+        fn build(constructor: &mut Container) -> Self {
+            HasDep {
+                unit: constructor.get(),
+            }
+        }
+    }
+
+    struct GenericDep<T> {
+        dep: Arc<T>,
+    }
+
+    impl<T> Build for GenericDep<T>
+    where
+        T: Build,
+    {
+        fn build(constructor: &mut Container) -> Self {
+            GenericDep {
+                dep: constructor.get(),
+            }
+        }
+    }
+
+    #[test]
+    fn unit_value() {
+        let mut c = Container::new(());
+
+        let _: Arc<Unit> = c.get::<Unit>();
+    }
+
+    #[test]
+    fn get_keeps_one_stored_reference() {
+        let mut c = Container::new(());
+
+        let built: Arc<HasDep> = c.get();
+        assert_eq!(Arc::strong_count(&built), 2);
+        // Stored, held by HasDep, and the one just returned.
+        assert_eq!(Arc::strong_count(&c.get::<Unit>()), 3);
+
+        let got: Arc<HasDep> = c.get();
+        assert!(Arc::ptr_eq(&built, &got));
+        assert_eq!(Arc::strong_count(&built), 3);
+    }
+
+    #[test]
+    fn default_container() {
+        let mut c = Container::default();
+
+        let _: Arc<Unit> = c.get::<Unit>();
+    }
+
+    #[test]
+    fn reuses_previous_constructed_values() {
+        let mut c = Container::new(());
+
+        let first: Arc<Counter> = c.get::<Counter>();
+        let second: Arc<Counter> = c.get::<Counter>();
+
+        assert_eq!(first.0, second.0);
+    }
+
+    #[test]
+    fn dependents_use_inserted_value() {
+        let mut c = Container::new(());
+
+        struct Name(&'static str);
+
+        impl Build for Name {
+            fn build(_: &mut Container) -> Self {
+                unreachable!("inserted values are not built")
+            }
+        }
+
+        struct Greeter {
+            name: Arc<Name>,
+        }
+
+        impl Build for Greeter {
+            fn build(constructor: &mut Container) -> Self {
+                Greeter {
+                    name: constructor.get(),
+                }
+            }
+        }
+
+        let stub = c.insert(Name("stub"));
+        let greeter: Arc<Greeter> = c.get();
+
+        assert!(Arc::ptr_eq(&stub, &greeter.name));
+        assert_eq!(greeter.name.0, "stub");
+    }
+
+    #[test]
+    fn gets_with_registered_factory() {
+        let mut c = Container::new(());
+        c.register(|_| Counter(42));
+
+        let counter: Arc<Counter> = c.get();
+        assert_eq!(counter.0, 42);
+    }
+
+    #[test]
+    fn overrides_only_inside_closure() {
+        let mut c = Container::new(());
+
+        let counter = c.with_override(Counter(42), |c| {
+            let dep: Arc<GenericDep<Counter>> = c.get();
+            dep.dep.0
+        });
+
+        assert_eq!(counter, 42);
+        assert!(!c.contains::<Counter>());
+        assert!(!c.contains::<GenericDep<Counter>>());
+    }
+
+    #[test]
+    fn gets_all_registered_implementations() {
+        let mut c = Container::new(());
+
+        trait Handler {
+            fn name(&self) -> &'static str;
+        }
+
+        impl Handler for Unit {
+            fn name(&self) -> &'static str {
+                "unit"
+            }
+        }
+
+        impl Handler for Counter {
+            fn name(&self) -> &'static str {
+                "counter"
+            }
+        }
+
+        c.register_many::<dyn Handler, Unit>(|h| h);
+        c.register_many::<dyn Handler, Counter>(|h| h);
+
+        let handlers = c.get_all::<dyn Handler>();
+        let names: Vec<_> = handlers.iter().map(|h| h.name()).collect();
+        assert_eq!(names, ["unit", "counter"]);
+        assert!(c.contains::<Counter>());
+    }
+
+    #[test]
+    fn gets_all_with_borrowed_input() {
+        let config = String::from("config");
+        let mut c = Container::new(&config);
+
+        assert!(c.get_all::<dyn fmt::Debug>().is_empty());
+    }
+
+    #[test]
+    fn gets_bound_implementation() {
+        let mut c = Container::new(());
+
+        trait Greeter {
+            fn greet(&self) -> &'static str;
+        }
+
+        impl Greeter for Unit {
+            fn greet(&self) -> &'static str {
+                "hello"
+            }
+        }
+
+        c.bind::<dyn Greeter, Unit>(|g| g);
+
+        let greeter = c.get_bound::<dyn Greeter>();
+        assert_eq!(greeter.greet(), "hello");
+        assert!(Arc::ptr_eq(&greeter, &c.get_bound::<dyn Greeter>()));
+        assert!(c.contains::<Unit>());
+    }
+
+    #[test]
+    fn keeps_named_values_separate() {
+        let mut c = Container::new(());
+
+        c.insert_named("db_url", "postgres://".to_string());
+        c.insert_named("cache_url", "redis://".to_string());
+
+        let db: Arc<String> = c.get_named_or_build_with("db_url", |_| unreachable!());
+        let cache: Arc<String> = c.get_named_or_build_with("cache_url", |_| unreachable!());
+        assert_eq!(*db, "postgres://");
+        assert_eq!(*cache, "redis://");
+
+        let named: Arc<Counter> = c.get_named("counter");
+        let unnamed: Arc<Counter> = c.get();
+        assert!(!Arc::ptr_eq(&named, &unnamed));
+        assert!(Arc::ptr_eq(&named, &c.get_named("counter")));
+    }
+
+    #[test]
+    fn build_into_only_fills_empty_slot() {
+        let mut c = Container::new(());
+
+        let mut slot = None;
+        c.build_into::<Counter>(&mut slot);
+        let first = Arc::clone(slot.as_ref().unwrap());
+        assert!(Arc::ptr_eq(&first, &c.get()));
+
+        c.remove::<Counter>();
+        c.build_into::<Counter>(&mut slot);
+        assert!(Arc::ptr_eq(&first, slot.as_ref().unwrap()));
+        assert!(!c.contains::<Counter>());
+    }
+
+    #[test]
+    fn rebuilds_after_remove() {
+        let mut c = Container::new(());
+
+        let first: Arc<Counter> = c.get();
+        let removed = c.remove::<Counter>().unwrap();
+        let second: Arc<Counter> = c.get();
+
+        assert!(Arc::ptr_eq(&first, &removed));
+        assert_ne!(first.0, second.0);
+    }
+
+    #[test]
+    fn rebuilds_after_clear() {
+        let mut c = Container::new(());
+
+        let first: Arc<Counter> = c.get();
+        c.clear();
+        let second: Arc<Counter> = c.get();
+
+        assert_ne!(first.0, second.0);
+    }
+
+    #[test]
+    fn scopes_share_parent_values() {
+        let mut parent = Container::new(());
+        let counter: Arc<Counter> = parent.get();
+
+        struct Request(#[expect(unused)] u8);
+
+        impl Build for Request {
+            fn build(_: &mut Container) -> Self {
+                Request(0)
+            }
+        }
+
+        let mut first = parent.scope();
+        let mut second = parent.scope();
+
+        assert!(Arc::ptr_eq(&counter, &first.get()));
+        assert!(Arc::ptr_eq(&counter, &second.get()));
+
+        let first_request: Arc<Request> = first.get();
+        let second_request: Arc<Request> = second.get();
+        assert!(!Arc::ptr_eq(&first_request, &second_request));
+
+        drop(first);
+        assert!(!parent.contains::<Request>());
+    }
+
+    #[test]
+    fn clones_share_values() {
+        let mut c = Container::new(());
+        let first: Arc<Counter> = c.get();
+
+        let mut cloned = c.clone();
+        let second: Arc<Counter> = cloned.get();
+
+        assert_eq!(first.0, second.0);
+    }
+
+    #[test]
+    fn build_fresh_ignores_stored_value() {
+        let mut c = Container::new(());
+        let _: Arc<Counter> = c.get();
+
+        let first: Counter = c.build_fresh();
+        let second: Counter = c.build_fresh();
+
+        assert_ne!(first.0, second.0);
+    }
+
+    #[test]
+    fn calls_on_build_for_each_build() {
+        let mut c = Container::new(());
+
+        let count = Rc::new(std::cell::Cell::new(0));
+        c.set_on_build({
+            let count = Rc::clone(&count);
+            move |name| {
+                if name == any::type_name::<Counter>() {
+                    count.set(count.get() + 1);
+                }
+            }
+        });
+
+        let _: Arc<Counter> = c.get();
+        let _: Arc<Counter> = c.get();
+        let _: Counter = c.build();
+        let _: Counter = c.build();
+        let _: Arc<Unit> = c.get();
+
+        assert_eq!(count.get(), 3);
+    }
+
+    #[test]
+    fn wraps_each_build() {
+        let mut c = Container::new(());
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        c.set_wrapper({
+            let events = Rc::clone(&events);
+            move |name, build| {
+                let name = name.rsplit("::").next().unwrap();
+                events.borrow_mut().push(format!("enter {name}"));
+                build();
+                events.borrow_mut().push(format!("exit {name}"));
+            }
+        });
+
+        let _: Arc<HasDep> = c.get();
+
+        assert_eq!(
+            *events.borrow(),
+            ["enter HasDep", "enter Unit", "exit Unit", "exit HasDep"]
+        );
+    }
+
+    #[test]
+    fn get_or_default_stores_default() {
+        let mut c = Container::new(());
+
+        let cache: Arc<Mutex<Vec<u8>>> = c.get_or_default();
+        cache.lock().unwrap().push(1);
+
+        assert!(Arc::ptr_eq(&cache, &c.get_or_default()));
+        assert_eq!(*c.get_or_default::<Mutex<Vec<u8>>>().lock().unwrap(), [1]);
+    }
+
+    #[test]
+    fn get_or_build_with_runs_once() {
+        let mut c = Container::new(());
+
+        struct OneOff(u8);
+
+        let mut runs = 0;
+        let first = c.get_or_build_with(|_| {
+            runs += 1;
+            OneOff(1)
+        });
+        let second = c.get_or_build_with(|_| {
+            runs += 1;
+            OneOff(2)
+        });
+
+        assert_eq!(runs, 1);
+        assert_eq!(first.0, second.0);
+    }
+
+    #[test]
+    fn lists_built_type_names() {
+        let mut c = Container::new(());
+
+        let _: Arc<HasDep> = c.get();
+        c.remove::<HasDep>();
+        let _: Arc<Counter> = c.get();
+
+        assert_eq!(
+            c.built_type_names(),
+            [any::type_name::<Unit>(), any::type_name::<Counter>()]
+        );
+    }
+
+    #[test]
+    fn visits_built_types_in_reverse() {
+        let mut c = Container::new(());
+
+        let _: Arc<HasDep> = c.get();
+        let _: Arc<Counter> = c.get();
+
+        let mut teardown = Vec::new();
+        c.for_each_built_rev(|name| teardown.push(name));
+        assert_eq!(
+            teardown,
+            [
+                any::type_name::<Counter>(),
+                any::type_name::<HasDep>(),
+                any::type_name::<Unit>(),
+            ]
+        );
+    }
+
+    #[test]
+    fn gets_multiple_values() {
+        let mut c = Container::new(());
+
+        let (unit, counter) = c.get2::<Unit, Counter>();
+        assert!(Arc::ptr_eq(&unit, &c.get()));
+        assert!(Arc::ptr_eq(&counter, &c.get()));
+
+        let (_, _, has_dep) = c.get3::<Unit, Counter, HasDep>();
+        assert!(Arc::ptr_eq(&has_dep, &c.get()));
+    }
+
+    #[test]
+    fn gets_optional_only_when_provided() {
+        let mut c = Container::new(());
+
+        assert!(c.get_optional::<Counter>().is_none());
+        assert!(!c.contains::<Counter>());
+
+        c.register(|_| Counter(42));
+        assert_eq!(c.get_optional::<Counter>().unwrap().0, 42);
+        assert!(c.contains::<Counter>());
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn times_slow_builds() {
+        let mut c = Container::new(());
+
+        struct Slow;
+
+        impl Build for Slow {
+            fn build(_: &mut Container) -> Self {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                Slow
+            }
+        }
+
+        let _: Arc<HasDep> = c.get();
+        let _: Arc<Slow> = c.get();
+
+        let timings = c.build_timings();
+        let names: Vec<_> = timings.iter().map(|(name, _)| *name).collect();
+        assert_eq!(
+            names,
+            [
+                any::type_name::<Unit>(),
+                any::type_name::<HasDep>(),
+                any::type_name::<Slow>(),
+            ]
+        );
+        assert!(timings[2].1 >= std::time::Duration::from_millis(10));
+    }
+
+    #[test]
+    fn ensures_values_are_built() {
+        let mut c = Container::new(());
+
+        c.ensure::<HasDep>().ensure::<Counter>();
+
+        assert!(c.contains::<HasDep>());
+        assert!(c.contains::<Unit>());
+        assert!(c.contains::<Counter>());
+    }
+
+    #[test]
+    fn prebuilds_fluently() {
+        let c = Container::with_input(())
+            .prebuild::<HasDep>()
+            .prebuild::<Counter>();
+
+        assert!(c.contains::<HasDep>());
+        assert!(c.contains::<Unit>());
+        assert!(c.contains::<Counter>());
+    }
+
+    #[test]
+    fn gets_self_referential_value() {
+        let mut c = Container::new(());
+
+        struct Node {
+            this: Weak<Node>,
+            unit: Arc<Unit>,
+        }
+
+        let node = c.get_cyclic(|this, c| Node {
+            this: this.clone(),
+            unit: c.get(),
+        });
+
+        assert!(Arc::ptr_eq(&node, &node.this.upgrade().unwrap()));
+        assert!(Arc::ptr_eq(&node.unit, &c.get()));
+        assert!(Arc::ptr_eq(
+            &node,
+            &c.get_cyclic::<Node>(|_, _| unreachable!())
+        ));
+    }
+
+    #[test]
+    fn cycle_through_dependency_suggests_get_cyclic() {
+        struct Parent {
+            child: Arc<Child>,
+        }
+
+        impl Build for Parent {
+            fn build(container: &mut Container) -> Self {
+                Parent {
+                    child: container.get(),
+                }
+            }
+        }
+
+        struct Child {
+            parent: Weak<Parent>,
+        }
+
+        impl Build for Child {
+            fn build(container: &mut Container) -> Self {
+                Child {
+                    parent: Arc::downgrade(&container.get()),
+                }
+            }
+        }
+
+        let mut c = Container::new(());
+        let err = c.try_get::<Parent>().err().unwrap();
+        assert!(err.to_string().contains("get_cyclic"), "{err}");
+
+        let mut c = Container::new(());
+        let parent = c.get_cyclic(|parent, c| Parent {
+            child: c.insert(Child {
+                parent: parent.clone(),
+            }),
+        });
+        assert!(Arc::ptr_eq(
+            &parent,
+            &parent.child.parent.upgrade().unwrap()
+        ));
+    }
+
+    #[test]
+    fn get_ref_reads_stored_value() {
+        let mut c = Container::new(());
+
+        let first = c.get_ref::<Counter>().0;
+        let stored: Arc<Counter> = c.get();
+
+        assert_eq!(first, stored.0);
+    }
+
+    impl Build<u32> for u32 {
+        fn build(container: &mut Container<u32>) -> Self {
+            *container.input()
+        }
+    }
+
+    #[test]
+    fn depth_increases_in_nested_builds() {
+        struct Inner(usize);
+
+        impl Build for Inner {
+            fn build(container: &mut Container) -> Self {
+                Inner(container.depth())
+            }
+        }
+
+        struct Outer(usize, Arc<Inner>);
+
+        impl Build for Outer {
+            fn build(container: &mut Container) -> Self {
+                Outer(container.depth(), container.get())
+            }
+        }
+
+        let mut c = Container::new(());
+        assert_eq!(c.depth(), 0);
+
+        let outer: Arc<Outer> = c.get();
+        assert_eq!(outer.0, 1);
+        assert_eq!(outer.1 .0, 2);
+        assert_eq!(c.depth(), 0);
+    }
+
+    #[test]
+    fn errors_past_max_depth() {
+        let mut c = Container::new(());
+        c.set_max_depth(1);
+
+        let err = c.try_get::<HasDep>().err().unwrap();
+        assert!(matches!(err, ForgyError::DepthExceeded { .. }));
+        assert_eq!(err.path().len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max depth exceeded")]
+    fn panics_on_chain_past_max_depth() {
+        #[expect(unused)]
+        struct First(Arc<Second>);
+
+        impl Build for First {
+            fn build(container: &mut Container) -> Self {
+                First(container.get())
+            }
+        }
+
+        #[expect(unused)]
+        struct Second(Arc<Third>);
+
+        impl Build for Second {
+            fn build(container: &mut Container) -> Self {
+                Second(container.get())
+            }
+        }
+
+        #[expect(unused)]
+        struct Third(Arc<Unit>);
+
+        impl Build for Third {
+            fn build(container: &mut Container) -> Self {
+                Third(container.get())
+            }
+        }
+
+        let mut c = Container::new(());
+        c.set_max_depth(3);
+
+        let _: First = c.build();
+    }
+
+    #[test]
+    fn builds_with_other_input() {
+        struct Port(u16);
+
+        impl Build<u16> for Port {
+            fn build(container: &mut Container<u16>) -> Self {
+                Port(*container.input())
+            }
+        }
+
+        let c = Container::new(());
+
+        let port: Port = c.build_with_input(8080);
+        assert_eq!(port.0, 8080);
+        assert!(!c.contains::<Port>());
+    }
+
+    #[test]
+    fn build_with_input_continues_the_build() {
+        struct Outer;
+
+        impl Build for Outer {
+            fn build(container: &mut Container) -> Self {
+                container.build_with_input::<u16, Inner>(8080);
+                Outer
+            }
+        }
+
+        struct Inner;
+
+        impl Build<u16> for Inner {
+            fn build(container: &mut Container<u16>) -> Self {
+                container.build_with_input::<(), Outer>(());
+                Inner
+            }
+        }
+
+        let mut c = Container::new(());
+        let err = c.try_get::<Outer>().err().unwrap();
+        assert!(matches!(err, ForgyError::Cycle { .. }));
+        assert_eq!(err.repeated(), any::type_name::<Outer>());
+
+        c.set_max_depth(1);
+        let err = c.try_get::<Outer>().err().unwrap();
+        assert!(matches!(err, ForgyError::DepthExceeded { .. }));
     }
 
-    /// Get the already created T, or build and store a new T.
-    pub fn get<T: Build<I>>(&mut self) -> Arc<T> {
-        if let Some(got) = self.built.get::<Arc<T>>() {
-            return Arc::clone(&got);
-        }
+    #[test]
+    fn build_n_builds_distinct_values() {
+        let mut c = Container::new(());
 
-        let new = Arc::new(self.build());
-        self.built.insert(Arc::clone(&new));
-        new
+        let counts: HashSet<u8> = c.build_n::<Counter>(3).iter().map(|c| c.0).collect();
+        assert_eq!(counts.len(), 3);
+        assert!(!c.contains::<Counter>());
     }
 
-    /// Build and do not store a new T.
-    pub fn build<T: Build<I>>(&mut self) -> T {
-        let type_id = TypeId::of::<T>();
-        if self.stack.contains(&type_id) {
-            panic!("Cycle constructing {type_id:?}: {:?}", self.stack);
-        }
+    #[test]
+    fn get_weak_upgrades_while_stored() {
+        let mut c = Container::new(());
 
-        self.stack.push(type_id);
-        let new = T::build(self);
-        self.stack.pop();
+        let weak = c.get_weak::<Counter>();
+        let stored: Arc<Counter> = c.get();
+        assert!(Arc::ptr_eq(&weak.upgrade().unwrap(), &stored));
 
-        new
+        drop(c);
+        drop(stored);
+        assert!(weak.upgrade().is_none());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::sync::atomic::AtomicU8;
+    #[test]
+    fn get_cloned_returns_value() {
+        let mut c = Container::new(8);
 
-    use super::*;
+        let workers: u32 = c.get_cloned();
+        assert_eq!(workers, 8);
+        assert!(c.contains::<u32>());
+    }
 
-    struct Unit;
+    #[test]
+    fn counts_hits_and_misses() {
+        let mut c = Container::new(());
 
-    impl Build for Unit {
-        fn build(_: &mut Container) -> Self {
-            Unit
-        }
+        let _: Arc<Unit> = c.get();
+        let _: Arc<Unit> = c.get();
+
+        assert_eq!(c.stats(), ContainerStats { hits: 1, misses: 1 });
+        assert_eq!(c.prebuilt_count(), 1);
     }
 
-    struct Counter(u8);
+    #[test]
+    fn from_converts_input() {
+        let c = Container::<String>::from("config");
 
-    impl Build for Counter {
-        fn build(_: &mut Container) -> Self {
-            static CONSTRUCTED: AtomicU8 = AtomicU8::new(0);
-            Counter(CONSTRUCTED.fetch_add(1, std::sync::atomic::Ordering::SeqCst))
-        }
+        assert_eq!(c.input(), "config");
     }
 
-    struct HasDep {
-        #[expect(unused)]
-        unit: Arc<Unit>,
-    }
+    #[test]
+    fn overlay_reads_parent_values() {
+        let mut parent = Container::new(());
+        let unit: Arc<Unit> = parent.get();
+        let parent = Rc::new(parent);
 
-    impl Build for HasDep {
-        fn build(constructor: &mut Container) -> Self {
-            HasDep {
-                unit: constructor.get(),
-            }
-        }
+        let mut overlay = Container::overlay(Rc::clone(&parent), ());
+        assert!(Arc::ptr_eq(&overlay.get(), &unit));
+
+        let _: Arc<HasDep> = overlay.get();
+        assert!(!parent.contains::<HasDep>());
+        assert_eq!(overlay.built_type_names(), [any::type_name::<HasDep>()]);
+        assert_eq!(overlay.prebuilt_count(), 1);
     }
 
-    struct GenericDep<T> {
-        #[expect(unused)]
-        dep: Arc<T>,
+    #[test]
+    fn overlay_reads_parent_values_in_every_lookup() {
+        let mut parent = Container::new(());
+        let unit: Arc<Unit> = parent.get();
+        let string = parent.insert(String::from("parent"));
+        let parent = Rc::new(parent);
+
+        let mut overlay = Container::overlay(parent, ());
+        assert!(overlay.contains::<Unit>());
+        assert!(Arc::ptr_eq(&overlay.get_try().unwrap(), &unit));
+        assert!(Arc::ptr_eq(&overlay.get_optional().unwrap(), &string));
+        assert!(Arc::ptr_eq(
+            &overlay.get_or_build_with(|_| unreachable!()),
+            &string
+        ));
+        assert!(Arc::ptr_eq(
+            &overlay.get_cyclic(|_, _| unreachable!()),
+            &string
+        ));
+        assert_eq!(overlay.get_ref::<Unit>() as *const Unit, Arc::as_ptr(&unit));
     }
 
-    impl<T> Build for GenericDep<T>
-    where
-        T: Build,
-    {
-        fn build(constructor: &mut Container) -> Self {
-            GenericDep {
-                dep: constructor.get(),
-            }
-        }
+    #[test]
+    fn builds_registered_factory_by_name() {
+        let mut c = Container::new(());
+        c.register_dyn("counter", |c| c.build::<Counter>());
+        c.register_dyn("greeting", |_| "hello");
+
+        let counter = c.build_dyn("counter").unwrap();
+        assert!(counter.is::<Counter>());
+        let greeting = c.build_dyn("greeting").unwrap();
+        assert_eq!(*greeting.downcast::<&str>().unwrap(), "hello");
+        assert!(c.build_dyn("missing").is_none());
+        assert!(c.build_boxed::<Unit>().is::<Unit>());
     }
 
     #[test]
-    fn unit_value() {
+    #[should_panic(expected = "Cycle constructing recursive")]
+    fn panics_with_cycle_in_registered_factory() {
         let mut c = Container::new(());
+        c.register_dyn("recursive", |c| c.build_dyn("recursive"));
 
-        let _: Arc<Unit> = c.get::<Unit>();
+        c.build_dyn("recursive");
     }
 
     #[test]
-    fn reuses_previous_constructed_values() {
+    fn get_cached_does_not_build() {
         let mut c = Container::new(());
 
-        let first: Arc<Counter> = c.get::<Counter>();
-        let second: Arc<Counter> = c.get::<Counter>();
+        assert!(c.get_cached::<Unit>().is_none());
+        assert!(!c.contains::<Unit>());
 
-        assert_eq!(first.0, second.0);
+        let unit: Arc<Unit> = c.get();
+        assert!(Arc::ptr_eq(&c.get_cached().unwrap(), &unit));
+    }
+
+    #[test]
+    fn contains_only_after_get() {
+        let mut c = Container::new(());
+
+        assert!(!c.contains::<Unit>());
+        let _: Arc<Unit> = c.get();
+        assert!(c.contains::<Unit>());
     }
 
     #[test]
@@ -174,7 +1958,7 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
+    #[should_panic(expected = "Foo")]
     fn panics_with_cycle() {
         let mut c = Container::new(());
 
@@ -199,29 +1983,340 @@ mod tests {
         let _: Arc<Foo> = c.get();
     }
 
+    #[test]
+    fn try_get_returns_cycle_error() {
+        let mut c = Container::new(());
+
+        #[expect(unused)]
+        struct Foo(Arc<Bar>);
+
+        impl Build for Foo {
+            fn build(constructor: &mut Container) -> Self {
+                Foo(constructor.get())
+            }
+        }
+
+        #[expect(unused)]
+        struct Bar(Arc<Foo>);
+
+        impl Build for Bar {
+            fn build(constructor: &mut Container) -> Self {
+                Bar(constructor.get())
+            }
+        }
+
+        let result = c.try_get::<Foo>();
+        assert!(matches!(result, Err(ForgyError::Cycle { .. })));
+    }
+
+    #[test]
+    fn reports_full_cycle_path() {
+        let mut c = Container::new(());
+
+        #[expect(unused)]
+        struct Root(Arc<A>);
+
+        impl Build for Root {
+            fn build(constructor: &mut Container) -> Self {
+                Root(constructor.get())
+            }
+        }
+
+        #[expect(unused)]
+        struct A(Arc<B>);
+
+        impl Build for A {
+            fn build(constructor: &mut Container) -> Self {
+                A(constructor.get())
+            }
+        }
+
+        #[expect(unused)]
+        struct B(Arc<C>);
+
+        impl Build for B {
+            fn build(constructor: &mut Container) -> Self {
+                B(constructor.get())
+            }
+        }
+
+        #[expect(unused)]
+        struct C(Arc<A>);
+
+        impl Build for C {
+            fn build(constructor: &mut Container) -> Self {
+                C(constructor.get())
+            }
+        }
+
+        let err = c.try_get::<Root>().err().unwrap();
+        let names = [
+            any::type_name::<Root>(),
+            any::type_name::<A>(),
+            any::type_name::<B>(),
+            any::type_name::<C>(),
+            any::type_name::<A>(),
+        ];
+        assert_eq!(err.path(), names);
+        assert_eq!(err.repeated(), any::type_name::<A>());
+        assert_eq!(err.cycle_start(), 1);
+    }
+
+    #[tokio::test]
+    async fn builds_async_with_dependency() {
+        let mut c = Container::new(());
+
+        struct Pool;
+
+        impl BuildAsync for Pool {
+            async fn build_async(_: &mut Container) -> Self {
+                tokio::task::yield_now().await;
+                Pool
+            }
+        }
+
+        struct Repo {
+            pool: Arc<Pool>,
+        }
+
+        impl BuildAsync for Repo {
+            async fn build_async(constructor: &mut Container) -> Self {
+                Repo {
+                    pool: constructor.get_async().await,
+                }
+            }
+        }
+
+        let repo: Arc<Repo> = c.get_async().await;
+        let pool: Arc<Pool> = c.get_async().await;
+        assert!(Arc::ptr_eq(&repo.pool, &pool));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Cycle")]
+    async fn panics_with_cycle_under_async_build() {
+        let mut c = Container::new(());
+
+        #[expect(unused)]
+        struct Foo(Arc<Bar>);
+
+        impl Build for Foo {
+            fn build(constructor: &mut Container) -> Self {
+                Foo(constructor.get())
+            }
+        }
+
+        #[expect(unused)]
+        struct Bar(Arc<Foo>);
+
+        impl Build for Bar {
+            fn build(constructor: &mut Container) -> Self {
+                Bar(constructor.get())
+            }
+        }
+
+        struct Repo;
+
+        impl BuildAsync for Repo {
+            async fn build_async(constructor: &mut Container) -> Self {
+                constructor.get::<Foo>();
+                Repo
+            }
+        }
+
+        let _: Arc<Repo> = c.get_async().await;
+    }
+
+    #[test]
+    fn get_try_propagates_nested_error() {
+        let mut c = Container::new(());
+
+        struct Port(#[expect(unused)] u16);
+
+        impl TryBuild for Port {
+            fn try_build(_: &mut Container) -> Result<Self, BuildError> {
+                Ok(Port("not a port".parse()?))
+            }
+        }
+
+        struct Server {
+            #[expect(unused)]
+            port: Arc<Port>,
+            #[expect(unused)]
+            unit: Arc<Unit>,
+        }
+
+        impl TryBuild for Server {
+            fn try_build(constructor: &mut Container) -> Result<Self, BuildError> {
+                Ok(Server {
+                    port: constructor.get_try()?,
+                    unit: constructor.get_try()?,
+                })
+            }
+        }
+
+        let err = c.get_try::<Server>().err().unwrap();
+        assert_eq!(err.to_string(), "invalid digit found in string");
+        assert!(!c.contains::<Server>());
+        assert!(c.get_try::<Unit>().is_ok());
+    }
+
+    #[test]
+    fn builds_after_constructor_panics() {
+        let mut c = Container::new(());
+
+        struct Flaky;
+
+        impl Build for Flaky {
+            fn build(_: &mut Container) -> Self {
+                static PANICKED: AtomicBool = AtomicBool::new(false);
+                if !PANICKED.swap(true, Ordering::SeqCst) {
+                    panic!("first build fails");
+                }
+                Flaky
+            }
+        }
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| c.get::<Flaky>()));
+        assert!(result.is_err());
+
+        let _: Arc<Flaky> = c.get();
+    }
+
+    #[derive(Clone, Debug)]
     struct Config {
         string: String,
     }
 
+    struct Dep {
+        string_from_config: String,
+    }
+
+    impl Build<Config> for Dep {
+        fn build(constructor: &mut Container<Config>) -> Self {
+            Dep {
+                string_from_config: constructor.input().string.clone(),
+            }
+        }
+    }
+
+    impl Build<&Config> for Dep {
+        fn build(constructor: &mut Container<&Config>) -> Self {
+            Dep {
+                string_from_config: constructor.input().string.clone(),
+            }
+        }
+    }
+
     #[test]
-    fn can_get_values_from_configuration() {
+    fn debug_shows_input_and_built_types() {
         let mut c = Container::new(Config {
             string: "some string".to_string(),
         });
+        c.insert(Unit);
 
-        struct Dep {
-            string_from_config: String,
-        }
+        let debug = format!("{c:?}");
+        assert!(
+            debug.contains(r#"Config { string: "some string" }"#),
+            "{debug}"
+        );
+        assert!(debug.contains(any::type_name::<Unit>()), "{debug}");
+    }
 
-        impl Build<Config> for Dep {
-            fn build(constructor: &mut Container<Config>) -> Self {
-                Dep {
-                    string_from_config: constructor.input().string.clone(),
-                }
-            }
-        }
+    #[test]
+    fn input_arc_shares_a_clone() {
+        let mut c = Container::new(Config {
+            string: "some string".to_string(),
+        });
+
+        let config = c.input_arc();
+        assert_eq!(config.string, "some string");
+        assert!(Arc::ptr_eq(&config, &c.input_arc()));
+    }
+
+    #[test]
+    fn can_get_values_from_configuration() {
+        let mut c = Container::new(Config {
+            string: "some string".to_string(),
+        });
 
         let dep: Arc<Dep> = c.get();
         assert_eq!(dep.string_from_config, "some string");
     }
+
+    #[test]
+    fn builds_with_mutated_input() {
+        let mut c = Container::new(Config {
+            string: "before".to_string(),
+        });
+
+        c.input_mut().string = "after".to_string();
+
+        let dep: Dep = c.build();
+        assert_eq!(dep.string_from_config, "after");
+    }
+
+    #[test]
+    fn builds_with_borrowed_input() {
+        let config = Config {
+            string: "borrowed".to_string(),
+        };
+        let mut c = Container::new(&config);
+
+        let dep: Arc<Dep> = c.get();
+        assert_eq!(dep.string_from_config, "borrowed");
+    }
+
+    #[test]
+    fn gets_stored_values_after_take_input() {
+        let mut c = Container::new(Config {
+            string: "some string".to_string(),
+        });
+
+        let first: Arc<Dep> = c.get();
+        let input = c.take_input().unwrap();
+        let second: Arc<Dep> = c.get();
+
+        assert_eq!(input.string, "some string");
+        assert_eq!(second.string_from_config, "some string");
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(c.take_input().is_none());
+    }
+
+    #[test]
+    fn rebuilds_with_replaced_input() {
+        let mut c = Container::new(Config {
+            string: "before".to_string(),
+        });
+
+        let before: Arc<Dep> = c.get();
+        let old = c.replace_input(Config {
+            string: "after".to_string(),
+        });
+        assert_eq!(old.string, "before");
+        assert!(Arc::ptr_eq(&before, &c.get()));
+
+        c.clear();
+        let after: Arc<Dep> = c.get();
+        assert_eq!(after.string_from_config, "after");
+    }
+
+    #[test]
+    fn builds_with_mapped_input() {
+        struct App {
+            config: Config,
+        }
+
+        let c = Container::new(App {
+            config: Config {
+                string: "sub".to_string(),
+            },
+        });
+
+        let mut sub = c.map_input(|app| Config {
+            string: app.config.string.clone(),
+        });
+        let dep: Arc<Dep> = sub.get();
+        assert_eq!(dep.string_from_config, "sub");
+    }
 }
@@ -0,0 +1,60 @@
+use std::{cell::RefCell, marker::PhantomData, sync::Arc};
+
+use crate::{Build, Container};
+
+/// Resolves a T on demand, as many times as needed.
+///
+/// Building a Provider takes a [Container::scope] of the Container, like [Lazy](crate::Lazy), so T
+/// shares the values stored at that point, and anything built for T is stored only in the
+/// Provider's scope. Each [Provider::get] is a [Container::get] on that scope, so a
+/// [Build::TRANSIENT] T is new every time and any other T is built once.
+pub struct Provider<T, I = ()> {
+    container: RefCell<Container<I>>,
+    ty: PhantomData<fn() -> T>,
+}
+
+impl<T: Build<I>, I: Clone + 'static> Provider<T, I> {
+    /// Get a T from the Provider's scope.
+    ///
+    /// Panics if constructing T encounters a cycle, or if called while building a T for this
+    /// Provider.
+    pub fn get(&self) -> Arc<T> {
+        self.container.borrow_mut().get()
+    }
+}
+
+impl<T: Build<I>, I: Clone + 'static> Build<I> for Provider<T, I> {
+    fn build(container: &mut Container<I>) -> Self {
+        Provider {
+            container: RefCell::new(container.scope()),
+            ty: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU8, Ordering};
+
+    use super::*;
+
+    struct Request(u8);
+
+    impl Build for Request {
+        const TRANSIENT: bool = true;
+
+        fn build(_: &mut Container) -> Self {
+            static BUILT: AtomicU8 = AtomicU8::new(0);
+            Request(BUILT.fetch_add(1, Ordering::SeqCst))
+        }
+    }
+
+    #[test]
+    fn gets_on_every_call() {
+        let mut c = Container::new(());
+
+        let provider: Provider<Request> = c.build();
+        assert_eq!(provider.get().0, 0);
+        assert_eq!(provider.get().0, 1);
+    }
+}
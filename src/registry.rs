@@ -0,0 +1,37 @@
+use std::{
+    any::{Any, TypeId},
+    sync::Arc,
+};
+
+use crate::Container;
+
+/// An implementation of a trait added with [provides](crate::provides), found at link time.
+#[doc(hidden)]
+pub struct Registration {
+    /// The `TypeId` of the trait object.
+    pub ty: fn() -> TypeId,
+
+    /// Get the implementation as an `Arc<dyn Trait>` from a `&mut Container<I>`, or `None` if the
+    /// Container has a different input than the implementation is built from.
+    pub resolve: fn(&mut dyn Any) -> Option<Box<dyn Any>>,
+}
+
+inventory::collect!(Registration);
+
+impl<I: 'static> Container<I> {
+    /// Get every implementation of T from [Container::get_all], then every implementation added
+    /// with [provides](crate::provides) for this input, in no particular order.
+    pub fn get_provided<T: ?Sized + 'static>(&mut self) -> Vec<Arc<T>> {
+        let mut all = self.get_all();
+
+        let registrations = inventory::iter::<Registration>
+            .into_iter()
+            .filter(|p| (p.ty)() == TypeId::of::<T>());
+        for registration in registrations {
+            if let Some(provided) = (registration.resolve)(self) {
+                all.push(*provided.downcast::<Arc<T>>().unwrap());
+            }
+        }
+        all
+    }
+}
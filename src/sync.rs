@@ -0,0 +1,179 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, RwLock},
+    thread::{self, ThreadId},
+};
+
+use anymap::any::CloneAny;
+
+use crate::{ForgyError, TypeInfo};
+
+/// A type that can be constructed given the [SyncContainer].
+pub trait BuildSync<I = ()>: Send + Sync + 'static {
+    fn build(container: &SyncContainer<I>) -> Self;
+}
+
+/// A container for constructed objects that can be shared between threads.
+///
+/// Cycles are detected per thread, so independent types can be resolved concurrently.
+pub struct SyncContainer<I = ()> {
+    input: I,
+    built: RwLock<anymap::Map<dyn CloneAny + Send + Sync>>,
+
+    stacks: Mutex<HashMap<ThreadId, Vec<TypeInfo>>>,
+}
+
+impl<I> SyncContainer<I> {
+    /// Construct a new SyncContainer with the provided input.
+    pub fn new(input: I) -> SyncContainer<I> {
+        SyncContainer {
+            input,
+            built: RwLock::new(anymap::Map::new()),
+            stacks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get a reference to the provided input.
+    pub fn input(&self) -> &I {
+        &self.input
+    }
+
+    /// Get the already created T, or build and store a new T.
+    ///
+    /// If two threads build T at the same time, both get the first one stored.
+    ///
+    /// Panics if constructing T encounters a cycle.
+    pub fn get<T: BuildSync<I>>(&self) -> Arc<T> {
+        if let Some(got) = self.built.read().unwrap().get::<Arc<T>>() {
+            return Arc::clone(got);
+        }
+
+        let new = Arc::new(self.build());
+        let mut built = self.built.write().unwrap();
+        Arc::clone(built.entry::<Arc<T>>().or_insert(new))
+    }
+
+    /// Build and do not store a new T.
+    ///
+    /// Panics if constructing T encounters a cycle.
+    pub fn build<T: BuildSync<I>>(&self) -> T {
+        let ty = TypeInfo::of::<T>();
+        let thread = thread::current().id();
+
+        let depth = {
+            let mut stacks = self.stacks.lock().unwrap();
+            let stack = stacks.entry(thread).or_default();
+            if stack.contains(&ty) {
+                let e = ForgyError::Cycle {
+                    ty,
+                    stack: stack.clone(),
+                };
+                drop(stacks);
+                panic!("{e}");
+            }
+
+            stack.push(ty);
+            stack.len() - 1
+        };
+
+        let _guard = StackGuard {
+            stacks: &self.stacks,
+            thread,
+            depth,
+        };
+        T::build(self)
+    }
+}
+
+/// Restores a thread's stack when its build finishes, even by panicking.
+struct StackGuard<'a> {
+    stacks: &'a Mutex<HashMap<ThreadId, Vec<TypeInfo>>>,
+    thread: ThreadId,
+    depth: usize,
+}
+
+impl Drop for StackGuard<'_> {
+    fn drop(&mut self) {
+        let mut stacks = self.stacks.lock().unwrap_or_else(|e| e.into_inner());
+        if self.depth == 0 {
+            stacks.remove(&self.thread);
+        } else if let Some(stack) = stacks.get_mut(&self.thread) {
+            stack.truncate(self.depth);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    struct Unit;
+
+    impl BuildSync for Unit {
+        fn build(_: &SyncContainer) -> Self {
+            Unit
+        }
+    }
+
+    struct Left(Arc<Unit>);
+
+    impl BuildSync for Left {
+        fn build(container: &SyncContainer) -> Self {
+            Left(container.get())
+        }
+    }
+
+    struct Right(Arc<Unit>);
+
+    impl BuildSync for Right {
+        fn build(container: &SyncContainer) -> Self {
+            Right(container.get())
+        }
+    }
+
+    #[test]
+    fn resolves_from_multiple_threads() {
+        let c = Arc::new(SyncContainer::new(()));
+
+        let left = thread::spawn({
+            let c = Arc::clone(&c);
+            move || c.get::<Left>()
+        });
+        let right = thread::spawn({
+            let c = Arc::clone(&c);
+            move || c.get::<Right>()
+        });
+
+        let left = left.join().unwrap();
+        let right = right.join().unwrap();
+        assert!(Arc::ptr_eq(&left.0, &right.0));
+        assert!(Arc::ptr_eq(&left, &c.get()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Foo")]
+    fn panics_with_cycle() {
+        #[expect(unused)]
+        struct Foo(Arc<Bar>);
+
+        impl BuildSync for Foo {
+            fn build(container: &SyncContainer) -> Self {
+                Foo(container.get())
+            }
+        }
+
+        #[expect(unused)]
+        struct Bar(Arc<Foo>);
+
+        impl BuildSync for Bar {
+            fn build(container: &SyncContainer) -> Self {
+                Bar(container.get())
+            }
+        }
+
+        let c = SyncContainer::new(());
+        let _: Arc<Foo> = c.get();
+    }
+}
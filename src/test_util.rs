@@ -0,0 +1,122 @@
+//! Helpers for testing components with stubbed dependencies.
+
+use std::{
+    any,
+    ops::{Deref, DerefMut},
+};
+
+use crate::Container;
+
+/// A [Container] for tests, set up with stubs in place of real dependencies.
+///
+/// Derefs to the Container, so components are resolved with [Container::get] as usual.
+pub struct MockContainer<I = ()> {
+    container: Container<I>,
+}
+
+impl<I> MockContainer<I> {
+    /// Construct a new MockContainer with the provided input and no stubs.
+    pub fn new(input: I) -> MockContainer<I> {
+        MockContainer {
+            container: Container::new(input),
+        }
+    }
+
+    /// Store `value` as the T, so it's used instead of building one.
+    pub fn stub<T: 'static>(mut self, value: T) -> Self {
+        self.container.insert(value);
+        self
+    }
+
+    /// Panics unless a T has been built or stubbed.
+    #[track_caller]
+    pub fn assert_built<T: 'static>(&self) -> &Self {
+        assert!(
+            self.container.contains::<T>(),
+            "expected {} to be built",
+            any::type_name::<T>()
+        );
+        self
+    }
+
+    /// Panics if a T has been built or stubbed.
+    #[track_caller]
+    pub fn assert_not_built<T: 'static>(&self) -> &Self {
+        assert!(
+            !self.container.contains::<T>(),
+            "expected {} not to be built",
+            any::type_name::<T>()
+        );
+        self
+    }
+
+    /// The underlying Container.
+    pub fn into_inner(self) -> Container<I> {
+        self.container
+    }
+}
+
+impl<I> Deref for MockContainer<I> {
+    type Target = Container<I>;
+
+    fn deref(&self) -> &Container<I> {
+        &self.container
+    }
+}
+
+impl<I> DerefMut for MockContainer<I> {
+    fn deref_mut(&mut self) -> &mut Container<I> {
+        &mut self.container
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::Build;
+
+    struct Smtp(&'static str);
+
+    impl Build for Smtp {
+        fn build(_: &mut Container) -> Self {
+            unreachable!("stubbed")
+        }
+    }
+
+    struct Mailer;
+
+    impl Build for Mailer {
+        fn build(_: &mut Container) -> Self {
+            Mailer
+        }
+    }
+
+    struct Signup {
+        smtp: Arc<Smtp>,
+    }
+
+    impl Build for Signup {
+        fn build(container: &mut Container) -> Self {
+            Signup {
+                smtp: container.get(),
+            }
+        }
+    }
+
+    #[test]
+    fn resolves_stubs_into_component() {
+        let mut c = MockContainer::new(()).stub(Smtp("localhost:25"));
+
+        let signup: Arc<Signup> = c.get();
+        assert_eq!(signup.smtp.0, "localhost:25");
+        c.assert_built::<Signup>().assert_not_built::<Mailer>();
+    }
+
+    #[test]
+    #[should_panic(expected = "Mailer to be built")]
+    fn assert_built_panics_when_missing() {
+        MockContainer::new(()).assert_built::<Mailer>();
+    }
+}
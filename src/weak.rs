@@ -0,0 +1,100 @@
+use std::{
+    any::TypeId,
+    sync::{Arc, OnceLock, Weak},
+};
+
+use crate::{Build, Container};
+
+/// A weak reference to the stored T, for back-references that would otherwise be cycles.
+///
+/// Building a WeakRef never builds T. If T is already stored it refers to that T, otherwise it
+/// refers to the next T the [Container] stores. So a parent that owns a child can be referred to by
+/// the child while the parent is still being built, as long as the parent is stored with
+/// [Container::get] or [Container::insert] afterwards. Until then, [WeakRef::upgrade] returns
+/// `None`.
+pub struct WeakRef<T> {
+    slot: Arc<OnceLock<Weak<T>>>,
+}
+
+type Slot<T> = Arc<OnceLock<Weak<T>>>;
+
+impl<T> WeakRef<T> {
+    /// Get the T if it has been stored and is still alive.
+    pub fn upgrade(&self) -> Option<Arc<T>> {
+        self.slot.get()?.upgrade()
+    }
+}
+
+impl<T: 'static, I> Build<I> for WeakRef<T> {
+    fn build(container: &mut Container<I>) -> Self {
+        let slot = Slot::<T>::default();
+        match container.get_cached::<T>() {
+            Some(strong) => {
+                let _ = slot.set(Arc::downgrade(&strong));
+            }
+            None => container
+                .weak
+                .entry(TypeId::of::<T>())
+                .or_default()
+                .push(Box::new(Arc::clone(&slot))),
+        }
+        WeakRef { slot }
+    }
+}
+
+/// Point every WeakRef waiting for a T at `value`.
+pub(crate) fn resolve<T: ?Sized + 'static, I>(container: &mut Container<I>, value: &Arc<T>) {
+    let Some(slots) = container.weak.remove(&TypeId::of::<T>()) else {
+        return;
+    };
+    for slot in slots {
+        let _ = slot
+            .downcast_ref::<Slot<T>>()
+            .unwrap()
+            .set(Arc::downgrade(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Parent {
+        child: Arc<Child>,
+    }
+
+    impl Build for Parent {
+        fn build(container: &mut Container) -> Self {
+            Parent {
+                child: container.get(),
+            }
+        }
+    }
+
+    struct Child {
+        parent: WeakRef<Parent>,
+    }
+
+    impl Build for Child {
+        fn build(container: &mut Container) -> Self {
+            Child {
+                parent: container.build(),
+            }
+        }
+    }
+
+    #[test]
+    fn refers_back_to_parent() {
+        let mut c = Container::new(());
+
+        let parent: Arc<Parent> = c.get();
+        let back = parent.child.parent.upgrade().unwrap();
+        assert!(Arc::ptr_eq(&parent, &back));
+
+        drop(back);
+        drop(parent);
+        c.clear();
+        let child: Arc<Child> = c.get();
+        assert!(child.parent.upgrade().is_none());
+    }
+}
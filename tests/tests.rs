@@ -27,6 +27,52 @@ fn derives_on_tuple() {
     let _: Arc<Tuple> = c.get();
 }
 
+#[test]
+fn derives_on_tuple_of_values() {
+    #[derive(Build)]
+    struct Limits(#[forgy(value = 5)] u32, #[forgy(value = 10)] u64);
+
+    let mut c = forgy::Container::new(());
+
+    let limits: Arc<Limits> = c.get();
+    assert_eq!(limits.0, 5);
+    assert_eq!(limits.1, 10);
+}
+
+#[test]
+fn derives_on_tuple_of_deps() {
+    #[derive(Build)]
+    struct Left;
+
+    #[derive(Build)]
+    struct Right;
+
+    #[derive(Build)]
+    struct Pair(Arc<Left>, Arc<Right>);
+
+    let mut c = forgy::Container::new(());
+
+    let pair: Arc<Pair> = c.get();
+    assert!(Arc::ptr_eq(&pair.0, &c.get()));
+    assert!(Arc::ptr_eq(&pair.1, &c.get()));
+}
+
+#[test]
+fn derives_on_mixed_tuple() {
+    #[derive(Build)]
+    struct Dep;
+
+    #[derive(Build)]
+    struct Mixed(#[forgy(value = 5)] u32, Arc<Dep>, #[forgy(value = 7)] u32);
+
+    let mut c = forgy::Container::new(());
+
+    let mixed: Arc<Mixed> = c.get();
+    assert_eq!(mixed.0, 5);
+    assert!(Arc::ptr_eq(&mixed.1, &c.get()));
+    assert_eq!(mixed.2, 7);
+}
+
 #[test]
 fn derives_on_struct() {
     #[derive(Build)]
@@ -114,3 +160,1238 @@ fn constructs_with_value() {
     let s: Arc<Struct> = c.get();
     assert_eq!(s.max_tasks, 16);
 }
+
+#[test]
+fn constructs_default_attribute() {
+    #[derive(Build)]
+    struct Struct {
+        #[forgy(default)]
+        some_cache: std::collections::HashSet<String>,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let s: Arc<Struct> = c.get();
+    assert!(s.some_cache.is_empty());
+}
+
+#[test]
+fn binds_trait_object_to_impl() {
+    trait Repository {
+        fn name(&self) -> &'static str;
+    }
+
+    #[derive(Build)]
+    struct PostgresRepo;
+
+    impl Repository for PostgresRepo {
+        fn name(&self) -> &'static str {
+            "postgres"
+        }
+    }
+
+    #[derive(Build)]
+    struct Service {
+        #[forgy(impl = PostgresRepo)]
+        repo: Arc<dyn Repository>,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let s: Arc<Service> = c.get();
+    assert_eq!(s.repo.name(), "postgres");
+}
+
+#[test]
+fn value_can_use_container() {
+    struct Input {
+        base_url: String,
+    }
+
+    #[derive(Build)]
+    struct Client {
+        #[forgy(value = String::from("/api"))]
+        prefix: String,
+    }
+
+    #[derive(Build)]
+    #[forgy(input = Input)]
+    struct Struct {
+        #[forgy(value = {
+            let client = container.get::<Client>();
+            format!("{}{}", container.input().base_url, client.prefix)
+        })]
+        url: String,
+    }
+
+    let mut c = forgy::Container::new(Input {
+        base_url: "https://example.com".to_string(),
+    });
+
+    let s: Arc<Struct> = c.get();
+    assert_eq!(s.url, "https://example.com/api");
+}
+
+#[test]
+fn derives_with_required_inputs() {
+    struct Config {
+        name: String,
+    }
+
+    struct Secrets {
+        token: String,
+    }
+
+    struct Input {
+        config: Config,
+        secrets: Secrets,
+    }
+
+    impl AsRef<Config> for Input {
+        fn as_ref(&self) -> &Config {
+            &self.config
+        }
+    }
+
+    impl AsRef<Secrets> for Input {
+        fn as_ref(&self) -> &Secrets {
+            &self.secrets
+        }
+    }
+
+    #[derive(Build)]
+    #[forgy(requires(Config, Secrets))]
+    struct Struct {
+        #[forgy(value = { let config: &Config = input.as_ref(); config.name.clone() })]
+        name: String,
+        #[forgy(value = { let secrets: &Secrets = input.as_ref(); secrets.token.clone() })]
+        token: String,
+    }
+
+    let mut c = forgy::Container::new(Input {
+        config: Config {
+            name: "app".to_string(),
+        },
+        secrets: Secrets {
+            token: "hunter2".to_string(),
+        },
+    });
+
+    let s: Arc<Struct> = c.get();
+    assert_eq!(s.name, "app");
+    assert_eq!(s.token, "hunter2");
+}
+
+#[test]
+fn resolves_named_values() {
+    #[derive(Build)]
+    struct Urls {
+        #[forgy(name = "db_url", value = "postgres://".to_string())]
+        db: Arc<String>,
+        #[forgy(name = "cache_url", value = "redis://".to_string())]
+        cache: Arc<String>,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let urls: Arc<Urls> = c.get();
+    assert_eq!(*urls.db, "postgres://");
+    assert_eq!(*urls.cache, "redis://");
+    assert!(Arc::ptr_eq(
+        &urls.db,
+        &c.get_named_or_build_with("db_url", |_| unreachable!())
+    ));
+}
+
+#[test]
+fn derives_on_generic_struct() {
+    #[derive(Build)]
+    struct Unit;
+
+    #[derive(Build)]
+    struct GenericDep<T> {
+        dep: Arc<T>,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let generic: Arc<GenericDep<Unit>> = c.get();
+    assert!(Arc::ptr_eq(&generic.dep, &c.get()));
+}
+
+#[test]
+fn derives_with_box_and_rc() {
+    use std::rc::Rc;
+
+    #[derive(Build)]
+    struct Unit;
+
+    #[derive(Build)]
+    struct Struct {
+        arc: Arc<Unit>,
+        boxed: Box<Unit>,
+        rc: Rc<Unit>,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let _: Arc<Struct> = c.get();
+}
+
+#[test]
+fn derives_generic_with_box() {
+    #[derive(Build)]
+    struct Unit;
+
+    #[derive(Build)]
+    struct GenericDep<T> {
+        dep: Box<T>,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let _: Arc<GenericDep<Unit>> = c.get();
+}
+
+#[test]
+fn builds_new_values_for_build_fields() {
+    use std::sync::atomic::{AtomicU8, Ordering};
+
+    struct Counter(u8);
+
+    impl<I> forgy::Build<I> for Counter {
+        fn build(_: &mut forgy::Container<I>) -> Self {
+            static CONSTRUCTED: AtomicU8 = AtomicU8::new(0);
+            Counter(CONSTRUCTED.fetch_add(1, Ordering::SeqCst))
+        }
+    }
+
+    #[derive(Build)]
+    struct Struct {
+        #[forgy(build)]
+        first: Arc<Counter>,
+        #[forgy(build)]
+        second: Arc<Counter>,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let s: Arc<Struct> = c.get();
+    assert_ne!(s.first.0, s.second.0);
+    assert!(!c.contains::<Counter>());
+}
+
+#[test]
+fn derives_with_borrowed_input() {
+    struct Input {
+        string: String,
+    }
+
+    #[derive(Build)]
+    #[forgy(input = &Input)]
+    struct Struct {
+        #[forgy(value = input.string.clone())]
+        from_input: String,
+    }
+
+    let input = Input {
+        string: "borrowed".to_string(),
+    };
+    let mut c = forgy::Container::new(&input);
+
+    let s: Arc<Struct> = c.get();
+    assert_eq!(s.from_input, "borrowed");
+}
+
+#[test]
+fn maps_dependency() {
+    #[derive(Build)]
+    struct Settings {
+        #[forgy(value = 4)]
+        workers: usize,
+    }
+
+    #[derive(Build)]
+    struct Pool {
+        #[forgy(from = Settings, map = dep.workers * 2)]
+        threads: usize,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let pool: Arc<Pool> = c.get();
+    assert_eq!(pool.threads, 8);
+}
+
+#[test]
+fn calls_on_built_after_storing() {
+    use std::sync::Mutex;
+
+    #[derive(Build)]
+    struct Bus {
+        #[forgy(default)]
+        handlers: Mutex<Vec<Arc<Handler>>>,
+    }
+
+    #[derive(Build)]
+    #[forgy(on_built)]
+    struct Handler;
+
+    impl forgy::OnBuilt for Handler {
+        fn on_built(self: &Arc<Self>, container: &mut forgy::Container) {
+            assert!(Arc::ptr_eq(self, &container.get()));
+            let bus = container.get::<Bus>();
+            bus.handlers.lock().unwrap().push(Arc::clone(self));
+        }
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let handler: Arc<Handler> = c.get();
+    let bus: Arc<Bus> = c.get();
+    let handlers = bus.handlers.lock().unwrap();
+    assert_eq!(handlers.len(), 1);
+    assert!(Arc::ptr_eq(&handlers[0], &handler));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn reads_config_fields() {
+    #[derive(Build)]
+    struct Server {
+        #[forgy(config)]
+        host: String,
+        #[forgy(config)]
+        port: u16,
+    }
+
+    let mut c = forgy::Container::new(serde_json::json!({
+        "host": "localhost",
+        "port": 8080,
+    }));
+
+    let server: Arc<Server> = c.get();
+    assert_eq!(server.host, "localhost");
+    assert_eq!(server.port, 8080);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn reads_config_fields_with_raw_names() {
+    #[derive(Build)]
+    struct Listener {
+        #[forgy(config)]
+        r#type: String,
+    }
+
+    let mut c = forgy::Container::new(serde_json::json!({ "type": "tcp" }));
+
+    let listener: Arc<Listener> = c.get();
+    assert_eq!(listener.r#type, "tcp");
+}
+
+#[test]
+fn value_can_fail_with_try() {
+    struct Input {
+        port: String,
+    }
+
+    #[derive(Build)]
+    #[forgy(input = Input, try)]
+    struct Server {
+        #[forgy(value = input.port.parse()?)]
+        port: u16,
+    }
+
+    let mut c = forgy::Container::new(Input {
+        port: "8080".to_string(),
+    });
+    assert_eq!(c.get_try::<Server>().unwrap().port, 8080);
+
+    let mut c = forgy::Container::new(Input {
+        port: "not a port".to_string(),
+    });
+    let err = c.get_try::<Server>().err().unwrap();
+    assert_eq!(err.to_string(), "invalid digit found in string");
+}
+
+#[test]
+fn try_resolves_dependencies() {
+    #[derive(Build)]
+    struct Unit;
+
+    #[derive(Build)]
+    #[forgy(input = (), try)]
+    struct Struct {
+        unit: Arc<Unit>,
+        #[forgy(value = "42".parse()?)]
+        answer: u8,
+    }
+
+    let mut c = forgy::Container::new(());
+    let s = c.get_try::<Struct>().unwrap();
+    assert_eq!(s.answer, 42);
+    assert!(Arc::ptr_eq(&s.unit, &c.get()));
+}
+
+#[test]
+fn try_builds_transient_dependencies_every_time() {
+    #[derive(Build)]
+    #[forgy(transient)]
+    struct Buffer;
+
+    #[derive(Build)]
+    #[forgy(input = (), try)]
+    struct Svc {
+        buffer: Arc<Buffer>,
+    }
+
+    let mut c = forgy::Container::new(());
+    let svc = c.get_try::<Svc>().unwrap();
+    assert!(!Arc::ptr_eq(&svc.buffer, &c.get()));
+    assert!(!c.contains::<Buffer>());
+}
+
+#[test]
+fn try_calls_on_built_for_dependencies() {
+    use std::sync::atomic::{AtomicU8, Ordering};
+
+    static CALLED: AtomicU8 = AtomicU8::new(0);
+
+    #[derive(Build)]
+    #[forgy(on_built)]
+    struct Handler;
+
+    impl forgy::OnBuilt for Handler {
+        fn on_built(self: &Arc<Self>, _: &mut forgy::Container) {
+            CALLED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[derive(Build)]
+    #[forgy(input = (), try)]
+    struct Svc {
+        handler: Arc<Handler>,
+    }
+
+    let mut c = forgy::Container::new(());
+    let svc = c.get_try::<Svc>().unwrap();
+    assert!(Arc::ptr_eq(&svc.handler, &c.get()));
+    assert_eq!(CALLED.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn child_refers_back_to_parent() {
+    #[derive(Build)]
+    struct Parent {
+        child: Arc<Child>,
+    }
+
+    #[derive(Build)]
+    struct Child {
+        parent: forgy::WeakRef<Parent>,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let parent: Arc<Parent> = c.get();
+    let back = parent.child.parent.upgrade().unwrap();
+    assert!(Arc::ptr_eq(&parent, &back));
+}
+
+#[test]
+fn shares_mutex_between_dependents() {
+    use std::sync::Mutex;
+
+    #[derive(Build)]
+    struct State {
+        #[forgy(default)]
+        events: Vec<&'static str>,
+    }
+
+    #[derive(Build)]
+    struct Writer {
+        state: Arc<Mutex<State>>,
+    }
+
+    #[derive(Build)]
+    struct Reader {
+        state: Arc<Mutex<State>>,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let writer: Arc<Writer> = c.get();
+    let reader: Arc<Reader> = c.get();
+    writer.state.lock().unwrap().events.push("written");
+    reader.state.lock().unwrap().events.push("read");
+
+    assert_eq!(writer.state.lock().unwrap().events, ["written", "read"]);
+    assert!(!c.contains::<State>());
+}
+
+#[test]
+fn derives_with_generic_input() {
+    trait Database: 'static {
+        fn url(&self) -> String;
+    }
+
+    struct Postgres;
+
+    impl Database for Postgres {
+        fn url(&self) -> String {
+            "postgres://".to_string()
+        }
+    }
+
+    struct AppState<Db> {
+        db: Db,
+    }
+
+    #[derive(Build)]
+    #[forgy(input = AppState<Db>, generics(Db: Database))]
+    struct Repo {
+        #[forgy(value = input.db.url())]
+        url: String,
+    }
+
+    #[derive(Build)]
+    #[forgy(input = AppState<Db>, generics(Db: Database))]
+    struct Service {
+        repo: Arc<Repo>,
+    }
+
+    let mut c = forgy::Container::new(AppState { db: Postgres });
+
+    let service: Arc<Service> = c.get();
+    assert_eq!(service.repo.url, "postgres://");
+}
+
+#[test]
+fn generates_new() {
+    #[derive(Build)]
+    struct Unit;
+
+    #[derive(Build)]
+    #[forgy(new)]
+    struct Struct {
+        unit: Arc<Unit>,
+        #[forgy(value = 16)]
+        max_tasks: u32,
+    }
+
+    #[derive(Build)]
+    #[forgy(new)]
+    struct Tuple(Arc<Unit>, #[forgy(default)] Vec<u8>);
+
+    let s = Struct::new(Arc::new(Unit), 4);
+    assert_eq!(s.max_tasks, 4);
+
+    let t = Tuple::new(Arc::new(Unit), vec![1]);
+    assert_eq!(t.1, [1]);
+}
+
+#[test]
+fn computes_fields_from_input_and_container() {
+    struct Input {
+        name: String,
+    }
+
+    #[derive(Build)]
+    struct Prefix {
+        #[forgy(value = String::from("hello "))]
+        prefix: String,
+    }
+
+    #[derive(Build)]
+    #[forgy(input = Input)]
+    struct Greeting {
+        #[forgy(value = input.name.clone())]
+        name: String,
+        #[forgy(value = container.get::<Prefix>().prefix.clone())]
+        prefix: String,
+    }
+
+    let mut c = forgy::Container::new(Input {
+        name: "world".to_string(),
+    });
+
+    let greeting: Arc<Greeting> = c.get();
+    assert_eq!(greeting.name, "world");
+    assert_eq!(greeting.prefix, "hello ");
+}
+
+#[test]
+fn computes_value_with_function() {
+    struct Input {
+        workers: usize,
+    }
+
+    struct Pool {
+        size: usize,
+    }
+
+    fn make_pool(input: &Input) -> Pool {
+        Pool {
+            size: input.workers * 2,
+        }
+    }
+
+    #[derive(Build)]
+    #[forgy(input = Input)]
+    struct Server {
+        #[forgy(value_with = make_pool)]
+        pool: Pool,
+    }
+
+    let mut c = forgy::Container::new(Input { workers: 4 });
+
+    let server: Arc<Server> = c.get();
+    assert_eq!(server.pool.size, 8);
+}
+
+#[test]
+fn chooses_value_with_value_if() {
+    struct Input {
+        fast: bool,
+    }
+
+    #[derive(Build)]
+    #[forgy(input = Input)]
+    struct Cache {
+        #[forgy(value_if = input.fast, value = 1024, else = 0)]
+        capacity: usize,
+    }
+
+    let mut c = forgy::Container::new(Input { fast: true });
+    assert_eq!(c.get::<Cache>().capacity, 1024);
+
+    let mut c = forgy::Container::new(Input { fast: false });
+    assert_eq!(c.get::<Cache>().capacity, 0);
+}
+
+#[test]
+fn optional_fields_resolve_when_inserted() {
+    struct Plugin(&'static str);
+
+    #[derive(Build)]
+    struct Host {
+        plugin: Option<Arc<Plugin>>,
+    }
+
+    let mut c = forgy::Container::new(());
+    assert!(c.build::<Host>().plugin.is_none());
+
+    c.insert(Plugin("metrics"));
+    assert_eq!(c.build::<Host>().plugin.unwrap().0, "metrics");
+}
+
+#[test]
+fn flattens_shared_dependencies() {
+    #[derive(Build)]
+    struct Db;
+
+    #[derive(Build)]
+    struct Logger;
+
+    #[derive(Build)]
+    struct CommonDeps {
+        db: Arc<Db>,
+        logger: Arc<Logger>,
+    }
+
+    #[derive(Build)]
+    struct Users {
+        #[forgy(flatten)]
+        common: CommonDeps,
+    }
+
+    #[derive(Build)]
+    struct Orders {
+        #[forgy(flatten)]
+        common: CommonDeps,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let users: Arc<Users> = c.get();
+    let orders: Arc<Orders> = c.get();
+    assert!(Arc::ptr_eq(&users.common.db, &orders.common.db));
+    assert!(!c.contains::<CommonDeps>());
+}
+
+#[cfg(feature = "registry")]
+#[test]
+fn collects_provided_implementations() {
+    trait Handler {
+        fn name(&self) -> &'static str;
+    }
+
+    #[derive(Build)]
+    struct Echo;
+
+    #[forgy::provides(dyn Handler)]
+    impl Handler for Echo {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+    }
+
+    #[derive(Build)]
+    struct Ping;
+
+    #[forgy::provides(dyn Handler)]
+    impl Handler for Ping {
+        fn name(&self) -> &'static str {
+            "ping"
+        }
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let mut names: Vec<_> = c
+        .get_provided::<dyn Handler>()
+        .iter()
+        .map(|h| h.name())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["echo", "ping"]);
+    #[derive(Build)]
+    struct Router {
+        #[forgy(collect = dyn Handler, provided)]
+        handlers: Vec<Arc<dyn Handler>>,
+    }
+
+    let router: Arc<Router> = c.get();
+    assert_eq!(router.handlers.len(), 2);
+}
+
+#[test]
+fn builds_transient_types_every_time() {
+    #[derive(Build)]
+    #[forgy(transient)]
+    struct Buffer {
+        #[forgy(value = vec![0; 1024])]
+        bytes: Vec<u8>,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let first: Arc<Buffer> = c.get();
+    let second: Arc<Buffer> = c.get();
+    assert!(!Arc::ptr_eq(&first, &second));
+    assert!(!c.contains::<Buffer>());
+}
+
+#[test]
+fn collects_registered_implementations() {
+    trait Step {
+        fn name(&self) -> &'static str;
+    }
+
+    #[derive(Build)]
+    struct Parse;
+
+    impl Step for Parse {
+        fn name(&self) -> &'static str {
+            "parse"
+        }
+    }
+
+    #[derive(Build)]
+    struct Render;
+
+    impl Step for Render {
+        fn name(&self) -> &'static str {
+            "render"
+        }
+    }
+
+    #[derive(Build)]
+    struct Pipeline {
+        #[forgy(collect = dyn Step)]
+        steps: Vec<Arc<dyn Step>>,
+    }
+
+    let mut c = forgy::Container::new(());
+    c.register_many::<dyn Step, Parse>(|s| s);
+    c.register_many::<dyn Step, Render>(|s| s);
+
+    let pipeline: Arc<Pipeline> = c.get();
+    let names: Vec<_> = pipeline.steps.iter().map(|s| s.name()).collect();
+    assert_eq!(names, ["parse", "render"]);
+}
+
+#[test]
+fn value_owned_clones_input() {
+    #[derive(Clone)]
+    struct Input {
+        name: String,
+    }
+
+    #[derive(Build)]
+    struct Prefix {
+        #[forgy(value = String::from("hello "))]
+        prefix: String,
+    }
+
+    #[derive(Build)]
+    #[forgy(input = Input)]
+    struct Greeting {
+        #[forgy(value_owned = format!("{}{}", container.get::<Prefix>().prefix, input.name))]
+        message: String,
+    }
+
+    let mut c = forgy::Container::new(Input {
+        name: "world".to_string(),
+    });
+
+    let greeting: Arc<Greeting> = c.get();
+    assert_eq!(greeting.message, "hello world");
+}
+
+#[test]
+fn describes_dependencies() {
+    trait Repository {}
+
+    #[derive(Build)]
+    struct Unit;
+
+    #[derive(Build)]
+    struct PostgresRepo;
+
+    impl Repository for PostgresRepo {}
+
+    #[derive(Build)]
+    #[forgy(describe)]
+    struct Service {
+        unit: Arc<Unit>,
+        #[forgy(impl = PostgresRepo)]
+        repo: Arc<dyn Repository>,
+        #[forgy(value = 16)]
+        max_tasks: u32,
+    }
+
+    assert_eq!(
+        Service::forgy_deps(),
+        [
+            ("unit", std::any::type_name::<Unit>()),
+            ("repo", std::any::type_name::<PostgresRepo>()),
+            ("max_tasks", "u32"),
+        ]
+    );
+}
+
+#[test]
+fn shares_cache_slot_with_cache_as() {
+    #[derive(Build)]
+    struct Pool {
+        #[forgy(value = 8)]
+        size: usize,
+    }
+
+    #[derive(Build)]
+    #[forgy(cache_as = Pool)]
+    #[repr(transparent)]
+    struct ReadPool(Pool);
+
+    #[derive(Build)]
+    #[forgy(cache_as = Pool)]
+    #[repr(transparent)]
+    struct WritePool {
+        pool: Pool,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let read: Arc<ReadPool> = c.get();
+    let write: Arc<WritePool> = c.get();
+    let pool: Arc<Pool> = c.get();
+    assert_eq!(read.0.size, 8);
+    assert_eq!(Arc::as_ptr(&read).cast::<Pool>(), Arc::as_ptr(&pool));
+    assert_eq!(Arc::as_ptr(&write).cast::<Pool>(), Arc::as_ptr(&pool));
+    assert!(Arc::ptr_eq(&read, &c.get()));
+}
+
+#[test]
+fn try_get_returns_cycle_through_cache_as() {
+    #[derive(Build)]
+    struct Pool {
+        #[expect(unused)]
+        read: Arc<ReadPool>,
+    }
+
+    #[derive(Build)]
+    #[forgy(cache_as = Pool)]
+    #[repr(transparent)]
+    struct ReadPool(Pool);
+
+    let mut c = forgy::Container::new(());
+    let err = c.try_get::<ReadPool>().err().unwrap();
+    assert!(matches!(err, forgy::ForgyError::Cycle { .. }));
+}
+
+#[test]
+fn try_shares_cache_slot_with_cache_as() {
+    #[derive(Build)]
+    struct Pool;
+
+    #[derive(Build)]
+    #[forgy(cache_as = Pool)]
+    #[repr(transparent)]
+    struct ReadPool(Pool);
+
+    #[derive(Build)]
+    #[forgy(input = (), try)]
+    struct Svc {
+        read: Arc<ReadPool>,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let pool: Arc<Pool> = c.get();
+    let svc = c.get_try::<Svc>().unwrap();
+    assert_eq!(Arc::as_ptr(&svc.read).cast::<Pool>(), Arc::as_ptr(&pool));
+}
+
+#[test]
+fn converts_dependency_with_into() {
+    #[derive(Build)]
+    struct Postgres;
+
+    struct Db(Arc<Postgres>);
+
+    impl From<Arc<Postgres>> for Db {
+        fn from(postgres: Arc<Postgres>) -> Self {
+            Db(postgres)
+        }
+    }
+
+    #[derive(Build)]
+    struct Repo {
+        #[forgy(from = Postgres, into)]
+        db: Db,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let repo: Arc<Repo> = c.get();
+    assert!(Arc::ptr_eq(&repo.db.0, &c.get()));
+}
+
+#[test]
+fn value_uses_earlier_fields() {
+    #[derive(Build)]
+    struct Sum {
+        #[forgy(value = 2)]
+        a: u32,
+        #[forgy(value = 3)]
+        b: u32,
+        #[forgy(value = a + b)]
+        sum: u32,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let sum: Arc<Sum> = c.get();
+    assert_eq!(sum.sum, 5);
+}
+
+fn timeout() -> u64 {
+    30
+}
+
+#[test]
+fn value_calls_shadowed_fn_by_path() {
+    #[derive(Build)]
+    struct Client {
+        #[forgy(value = 5)]
+        timeout: u64,
+        #[forgy(value = self::timeout() * 2)]
+        retry_after: u64,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let client: Arc<Client> = c.get();
+    assert_eq!(client.timeout, 5);
+    assert_eq!(client.retry_after, 60);
+}
+
+#[test]
+fn accepts_inline() {
+    #[derive(Build)]
+    #[forgy(inline)]
+    struct Unit;
+
+    #[derive(Build)]
+    #[forgy(inline(always))]
+    struct Wiring {
+        #[expect(unused)]
+        unit: Arc<Unit>,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let _: Arc<Wiring> = c.get();
+}
+
+#[test]
+fn renames_input_binding() {
+    struct Config {
+        workers: u32,
+    }
+
+    #[derive(Build)]
+    #[forgy(input = Config, rename_input = config)]
+    struct Pool {
+        #[forgy(value = config.workers * 2)]
+        threads: u32,
+    }
+
+    let mut c = forgy::Container::new(Config { workers: 4 });
+
+    let pool: Arc<Pool> = c.get();
+    assert_eq!(pool.threads, 8);
+}
+
+#[test]
+fn initializes_cfg_gated_fields() {
+    #[derive(Build)]
+    struct Metrics;
+
+    #[derive(Build)]
+    struct Server {
+        #[cfg(feature = "metrics")]
+        #[forgy(cfg(feature = "metrics"))]
+        metrics: Arc<Metrics>,
+        #[forgy(value = 8080)]
+        port: u16,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let server: Arc<Server> = c.get();
+    assert_eq!(server.port, 8080);
+    #[cfg(feature = "metrics")]
+    assert!(Arc::ptr_eq(&server.metrics, &c.get()));
+    #[cfg(not(feature = "metrics"))]
+    assert!(!c.contains::<Metrics>());
+}
+
+#[test]
+fn adds_where_predicates() {
+    #[derive(Build)]
+    #[forgy(where = "I: Clone")]
+    struct Snapshot {
+        #[forgy(value_with = Snapshot::taken)]
+        taken: bool,
+    }
+
+    impl Snapshot {
+        fn taken<I: Clone>(input: &I) -> bool {
+            let _ = input.clone();
+            true
+        }
+    }
+
+    let mut c = forgy::Container::new(String::from("state"));
+
+    let snapshot: Arc<Snapshot> = c.get();
+    assert!(snapshot.taken);
+}
+
+#[test]
+fn struct_level_value() {
+    #[derive(Build)]
+    #[forgy(value = 8080)]
+    struct Port(u16);
+
+    #[derive(Build)]
+    #[forgy(input = String, value = input.clone())]
+    struct Host {
+        name: String,
+    }
+
+    let mut c = forgy::Container::new(String::from("localhost"));
+
+    let port: Arc<Port> = c.get();
+    assert_eq!(port.0, 8080);
+    let host: Arc<Host> = c.get();
+    assert_eq!(host.name, "localhost");
+}
+
+#[test]
+fn provider_field_resolves_on_demand() {
+    #[derive(Build)]
+    struct Pool;
+
+    #[derive(Build)]
+    struct Handler {
+        pool: forgy::Provider<Pool>,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let handler: Arc<Handler> = c.get();
+    assert!(!c.contains::<Pool>());
+    assert!(Arc::ptr_eq(&handler.pool.get(), &handler.pool.get()));
+}
+
+#[test]
+fn validates_built_value() {
+    #[derive(Build)]
+    #[forgy(validate = |s| s.workers > 0)]
+    struct Pool {
+        #[forgy(value = 4)]
+        workers: u32,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let pool: Arc<Pool> = c.get();
+    assert_eq!(pool.workers, 4);
+}
+
+#[test]
+#[should_panic(expected = "`Pool` failed validation")]
+fn panics_when_validation_fails() {
+    #[derive(Build)]
+    #[forgy(validate = |s| s.workers > 0)]
+    struct Pool {
+        #[forgy(value = 0)]
+        workers: u32,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let _: Arc<Pool> = c.get();
+}
+
+#[test]
+fn try_validation_returns_error() {
+    struct Config {
+        url: &'static str,
+    }
+
+    #[derive(Build)]
+    #[forgy(input = Config, try, validate = |s| !s.url.is_empty())]
+    struct Client {
+        #[forgy(value = input.url)]
+        url: &'static str,
+    }
+
+    let mut c = forgy::Container::new(Config { url: "" });
+
+    assert!(c.get_try::<Client>().is_err());
+}
+
+#[test]
+fn fields_named_constructor_do_not_shadow_container() {
+    #[derive(Build)]
+    struct Unit;
+
+    #[derive(Build)]
+    struct Wiring {
+        #[forgy(value = { let constructor = 1; container.get::<Unit>(); constructor })]
+        constructor: u32,
+        #[forgy(value = constructor + 1)]
+        next: u32,
+        unit: Arc<Unit>,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let wiring: Arc<Wiring> = c.get();
+    assert_eq!(wiring.constructor, 1);
+    assert_eq!(wiring.next, 2);
+    assert!(Arc::ptr_eq(&wiring.unit, &c.get()));
+}
+
+#[test]
+fn resolves_through_type_aliases() {
+    trait Store {
+        fn len(&self) -> usize;
+    }
+
+    #[derive(Build)]
+    struct Memory<T: 'static> {
+        #[forgy(default)]
+        items: Vec<T>,
+    }
+
+    impl<T> Store for Memory<T> {
+        fn len(&self) -> usize {
+            self.items.len()
+        }
+    }
+
+    type Bytes = Memory<u8>;
+
+    #[derive(Build)]
+    struct Service {
+        bytes: Arc<Bytes>,
+        #[forgy(impl = Bytes)]
+        store: Arc<dyn Store>,
+        #[forgy(from = Bytes, map = dep.items.len())]
+        len: usize,
+        #[forgy(impl = Memory<u16>)]
+        wide: Arc<dyn Store>,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let service: Arc<Service> = c.get();
+    assert!(Arc::ptr_eq(&service.bytes, &c.get::<Memory<u8>>()));
+    assert_eq!(service.store.len(), 0);
+    assert_eq!(service.len, 0);
+    assert!(c.contains::<Memory<u16>>());
+}
+
+#[test]
+fn skipped_fields_are_default() {
+    use std::sync::atomic::{AtomicU8, Ordering};
+
+    #[derive(Build)]
+    struct Unit;
+
+    #[derive(Build)]
+    struct Counter {
+        #[expect(unused)]
+        unit: Arc<Unit>,
+        #[forgy(skip)]
+        count: AtomicU8,
+    }
+
+    let mut c = forgy::Container::new(());
+
+    let counter: Arc<Counter> = c.get();
+    assert_eq!(counter.count.fetch_add(1, Ordering::SeqCst), 0);
+}
+
+#[test]
+fn multi_statement_value() {
+    struct Config {
+        workers: u32,
+        fast: bool,
+    }
+
+    #[derive(Build)]
+    #[forgy(input = Config)]
+    struct Pool {
+        #[forgy(value = {
+            let base = input.workers * 2;
+            let extra = if input.fast { 1 } else { 0 };
+            if base > 4 { base + extra } else { 4 }
+        })]
+        threads: u32,
+    }
+
+    let mut c = forgy::Container::new(Config {
+        workers: 4,
+        fast: true,
+    });
+
+    let pool: Arc<Pool> = c.get();
+    assert_eq!(pool.threads, 9);
+}
@@ -0,0 +1,9 @@
+#[derive(forgy::Build)]
+struct Pool;
+
+#[derive(forgy::Build)]
+#[forgy(cache_as = Pool, on_built)]
+#[repr(transparent)]
+struct ReadPool(Pool);
+
+fn main() {}
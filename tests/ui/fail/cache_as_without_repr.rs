@@ -0,0 +1,8 @@
+#[derive(forgy::Build)]
+struct Pool;
+
+#[derive(forgy::Build)]
+#[forgy(cache_as = Pool)]
+struct ReadPool(Pool);
+
+fn main() {}
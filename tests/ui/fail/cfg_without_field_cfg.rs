@@ -0,0 +1,7 @@
+#[derive(forgy::Build)]
+struct Server {
+    #[forgy(cfg(feature = "metrics"), default)]
+    requests: u64,
+}
+
+fn main() {}
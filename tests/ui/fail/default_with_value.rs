@@ -0,0 +1,7 @@
+#[derive(forgy::Build)]
+struct Struct {
+    #[forgy(default, value = 16)]
+    max_tasks: u32,
+}
+
+fn main() {}
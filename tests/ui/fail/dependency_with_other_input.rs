@@ -0,0 +1,19 @@
+use std::sync::Arc;
+
+struct Config;
+
+struct Dep;
+
+impl forgy::Build for Dep {
+    fn build(_: &mut forgy::Container) -> Self {
+        Dep
+    }
+}
+
+#[derive(forgy::Build)]
+#[forgy(input = Config)]
+struct Struct {
+    dep: Arc<Dep>,
+}
+
+fn main() {}
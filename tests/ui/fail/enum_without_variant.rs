@@ -0,0 +1,7 @@
+#[derive(forgy::Build)]
+enum Mode {
+    Fast,
+    Slow,
+}
+
+fn main() {}
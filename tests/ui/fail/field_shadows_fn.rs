@@ -0,0 +1,13 @@
+fn timeout() -> u64 {
+    30
+}
+
+#[derive(forgy::Build)]
+struct Client {
+    #[forgy(value = 5)]
+    timeout: u64,
+    #[forgy(value = timeout() * 2)]
+    retry_after: u64,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[derive(forgy::Build)]
+struct Struct<'a> {
+    #[forgy(default)]
+    name: &'a str,
+}
+
+fn main() {}
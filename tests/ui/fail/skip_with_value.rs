@@ -0,0 +1,7 @@
+#[derive(forgy::Build)]
+struct Counter {
+    #[forgy(skip, value = 1)]
+    count: u8,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+#[derive(forgy::Build)]
+#[forgy(try)]
+struct Port {
+    #[forgy(value = "80".parse()?)]
+    port: u16,
+}
+
+fn main() {}
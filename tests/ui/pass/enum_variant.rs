@@ -0,0 +1,18 @@
+use std::sync::Arc;
+
+#[derive(forgy::Build)]
+struct Unit;
+
+#[derive(forgy::Build)]
+#[forgy(variant = Fast)]
+enum Mode {
+    Slow,
+    Fast(Arc<Unit>),
+}
+
+fn main() {
+    let mut c = forgy::Container::new(());
+
+    let mode: Arc<Mode> = c.get();
+    assert!(matches!(*mode, Mode::Fast(_)));
+}